@@ -0,0 +1,4 @@
+create table notes (
+    id integer primary key not null,
+    body text not null
+);
@@ -42,5 +42,5 @@ struct Search {
 }
 
 async fn search(Query(Search { q }): Query<Search>) -> String {
-    format!("{}?q={}", url!(search), q.unwrap_or_default())
+    url!(search, q = q)
 }
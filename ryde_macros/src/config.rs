@@ -0,0 +1,130 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    punctuated::Punctuated, Data, DataStruct, DeriveInput, Expr, ExprLit, Fields, GenericArgument,
+    Lit, MetaNameValue, PathArguments, Result, Token, Type,
+};
+
+pub fn derive_config_macro(input: DeriveInput) -> Result<TokenStream> {
+    let struct_ident = input.ident;
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => fields.named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                struct_ident,
+                "Only structs with named fields are supported",
+            ))
+        }
+    };
+
+    let mut lookups = vec![];
+    let mut values = vec![];
+    for field in &fields {
+        let ident = field.ident.as_ref().unwrap();
+        let mut key = ident.to_string().to_uppercase();
+        let mut default = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("config"))
+        {
+            let metas =
+                attr.parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)?;
+            for meta in metas {
+                let value = match &meta.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(value),
+                        ..
+                    }) => value.value(),
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "config values should be strings",
+                        ))
+                    }
+                };
+                match meta.path.get_ident().map(|ident| ident.to_string()) {
+                    Some(name) if name == "env" => key = value,
+                    Some(name) if name == "default" => default = Some(value),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            meta.path,
+                            "unknown config argument, expected env or default",
+                        ))
+                    }
+                }
+            }
+        }
+        let default = match default {
+            Some(default) => quote! { .or_else(|| Some(#default.to_string())) },
+            None => quote! {},
+        };
+        let (ty, missing) = match option_inner(&field.ty) {
+            Some(ty) => (ty, quote! {}),
+            None => (
+                &field.ty,
+                quote! { errors.push(format!("{} is missing", #key)); },
+            ),
+        };
+        lookups.push(quote! {
+            let #ident = match lookup(#key)#default {
+                Some(value) => match value.parse::<#ty>() {
+                    Ok(value) => Some(value),
+                    Err(err) => {
+                        errors.push(format!("{} is invalid: {}", #key, err));
+                        None
+                    }
+                },
+                None => {
+                    #missing
+                    None
+                }
+            };
+        });
+        values.push(match option_inner(&field.ty) {
+            Some(_) => quote! { #ident },
+            None => quote! { #ident: #ident.unwrap() },
+        });
+    }
+
+    Ok(quote! {
+        impl #struct_ident {
+            pub fn from_env() -> std::result::Result<Self, String> {
+                Self::from_lookup(|key| std::env::var(key).ok().or_else(|| ryde::dotenv(key)))
+            }
+
+            pub fn from_lookup(
+                lookup: impl Fn(&str) -> Option<String>,
+            ) -> std::result::Result<Self, String> {
+                let mut errors: Vec<String> = vec![];
+                #(#lookups)*
+
+                if !errors.is_empty() {
+                    return Err(errors.join(", "));
+                }
+
+                Ok(Self { #(#values,)* })
+            }
+        }
+    })
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -63,6 +63,15 @@ fn to_tokens(output: Output) -> TokenStream {
             Ok(Db(connection))
         }
 
+        impl Db {
+            pub async fn migrate(
+                &self,
+                migrations: &'static [(&'static str, &'static str)],
+            ) -> ryde::Result<Vec<&'static str>> {
+                ryde::migrate(&self.0, migrations).await
+            }
+        }
+
         pub trait Queries {
             #(#traits)*
         }
@@ -0,0 +1,69 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{FnArg, Ident, ItemFn, LitStr, PatType, Result};
+
+pub fn download_macro(filename: LitStr, input: ItemFn) -> Result<TokenStream> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = input;
+    let fn_ident = &sig.ident;
+    let mut args = vec![];
+    let mut bindings = vec![];
+    let mut params = vec![];
+
+    for (i, input) in sig.inputs.iter().enumerate() {
+        match input {
+            FnArg::Typed(PatType { pat, ty, .. }) => {
+                let arg = Ident::new(&format!("__ryde_arg{}", i), Span::call_site());
+                params.push(quote! { #arg: #ty });
+                bindings.push(quote! { let #pat = &#arg; });
+                args.push(arg);
+            }
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "#[download] only supports fn handlers",
+                ))
+            }
+        }
+    }
+
+    let asyncness = &sig.asyncness;
+    let generics = &sig.generics;
+    let where_clause = &sig.generics.where_clause;
+    let call = match asyncness {
+        Some(_) => quote! { #fn_ident(#(#args,)*).await },
+        None => quote! { #fn_ident(#(#args,)*) },
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #asyncness fn #fn_ident #generics(#(#params,)*) -> axum::response::Response #where_clause {
+            #[allow(unused_variables, unused_mut)]
+            let filename = {
+                #(#bindings)*
+                format!(#filename)
+            };
+
+            #sig #block
+
+            let mut response = axum::response::IntoResponse::into_response(#call);
+            if response.status().is_success() {
+                let disposition = format!(
+                    "attachment; filename=\"{}\"",
+                    filename.replace('\\', "\\\\").replace('"', "\\\"")
+                );
+                if let Ok(value) = axum::http::HeaderValue::try_from(disposition) {
+                    response
+                        .headers_mut()
+                        .insert(axum::http::header::CONTENT_DISPOSITION, value);
+                }
+            }
+
+            response
+        }
+    })
+}
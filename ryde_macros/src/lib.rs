@@ -1,17 +1,23 @@
+mod config;
 mod db;
+mod download;
 mod html;
+mod migrations;
 mod request_parts;
 mod routes;
 mod static_files;
 
+use config::derive_config_macro;
 use db::{db_macro, SqlExpr};
+use download::download_macro;
 use html::{component_macro, html_macro};
+use migrations::migrations_macro;
 use proc_macro::TokenStream;
 use quote::ToTokens;
 use request_parts::derive_request_parts_macro;
-use routes::{router_macro, routes_macro, url_macro, StateRouter, Url};
+use routes::{router_macro, routes_macro, url_macro, RouterArgs, StateRouter, Url};
 use static_files::static_files_macro;
-use syn::{parse_macro_input, punctuated::Punctuated, DeriveInput, Ident, ItemFn, Token};
+use syn::{parse_macro_input, punctuated::Punctuated, DeriveInput, Ident, ItemFn, LitStr, Token};
 
 #[proc_macro]
 pub fn db(input: TokenStream) -> TokenStream {
@@ -22,6 +28,15 @@ pub fn db(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro]
+pub fn migrations(input: TokenStream) -> TokenStream {
+    let folder = parse_macro_input!(input as LitStr);
+    match migrations_macro(folder) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 #[proc_macro]
 pub fn routes(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as StateRouter);
@@ -32,9 +47,10 @@ pub fn routes(input: TokenStream) -> TokenStream {
 }
 
 #[proc_macro_attribute]
-pub fn router(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn router(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as RouterArgs);
     let input = parse_macro_input!(input as ItemFn);
-    match router_macro(input) {
+    match router_macro(args, input) {
         Ok(s) => s.to_token_stream().into(),
         Err(e) => e.to_compile_error().into(),
     }
@@ -49,7 +65,7 @@ pub fn url(input: TokenStream) -> TokenStream {
     }
 }
 
-#[proc_macro_derive(StaticFiles, attributes(folder, prefix))]
+#[proc_macro_derive(StaticFiles, attributes(folder, file, prefix, mime))]
 pub fn static_files(s: TokenStream) -> TokenStream {
     let input = parse_macro_input!(s as DeriveInput);
     match static_files_macro(input) {
@@ -75,6 +91,15 @@ pub fn component(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(Config, attributes(config))]
+pub fn derive_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match derive_config_macro(input) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 #[proc_macro_derive(RequestParts)]
 pub fn derive_request_parts(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -83,3 +108,13 @@ pub fn derive_request_parts(input: TokenStream) -> TokenStream {
         Err(e) => e.to_compile_error().into(),
     }
 }
+
+#[proc_macro_attribute]
+pub fn download(args: TokenStream, input: TokenStream) -> TokenStream {
+    let filename = parse_macro_input!(args as LitStr);
+    let input = parse_macro_input!(input as ItemFn);
+    match download_macro(filename, input) {
+        Ok(s) => s.to_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
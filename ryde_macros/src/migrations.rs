@@ -0,0 +1,29 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{LitStr, Result};
+
+pub fn migrations_macro(folder: LitStr) -> Result<TokenStream> {
+    let path = std::env::current_dir().unwrap().join(folder.value());
+    let mut files = std::fs::read_dir(&path)
+        .map_err(|err| {
+            syn::Error::new_spanned(
+                &folder,
+                format!("could not read folder {}: {}", folder.value(), err),
+            )
+        })?
+        .filter_map(|dir_entry| dir_entry.ok())
+        .map(|dir_entry| dir_entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "sql"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let migrations = files.iter().map(|path| {
+        let name = path.file_name().unwrap().to_string_lossy();
+        let path = path.to_string_lossy();
+        quote! { (#name, include_str!(#path)) }
+    });
+
+    Ok(quote! {
+        &[#(#migrations,)*]
+    })
+}
@@ -31,7 +31,12 @@ pub fn derive_request_parts_macro(input: DeriveInput) -> Result<TokenStream> {
                 }
             })
             .collect::<Vec<_>>(),
-        Data::Enum(_) | Data::Union(_) => unimplemented!("Only structs are supported"),
+        Data::Enum(_) | Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                struct_ident,
+                "Only structs are supported",
+            ))
+        }
     };
 
     Ok(quote! {
@@ -2,149 +2,593 @@ use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use std::collections::HashSet;
 use syn::{
-    parse::Parse, punctuated::Punctuated, Expr, ExprCall, ExprLit, ExprMethodCall, ExprPath,
-    ExprTuple, Ident, ItemFn, Lit, LitStr, Result, Token,
+    parse::Parse, punctuated::Punctuated, spanned::Spanned, Expr, ExprAssign, ExprCall, ExprLit,
+    ExprMethodCall, ExprPath, ExprTuple, Ident, ItemFn, Lit, LitStr, MetaNameValue, Result, Token,
 };
 
-pub fn router_macro(input: ItemFn) -> Result<TokenStream> {
-    let mut parts: Vec<(String, Ident)> = vec![];
+pub fn router_macro(args: RouterArgs, mut input: ItemFn) -> Result<TokenStream> {
+    for stmt in &mut input.block.stmts {
+        if let syn::Stmt::Expr(expr, _) = stmt {
+            normalize_routes(expr, args.strip_trailing_slash);
+        }
+    }
 
-    input.block.stmts.iter().for_each(|stmt| match stmt {
-        syn::Stmt::Expr(expr, _) => router(&Box::new(expr.clone()), None, &mut parts),
-        _ => {}
+    let mut parts: Vec<(String, Ident, Option<syn::Path>)> = vec![];
+
+    for stmt in &input.block.stmts {
+        if let syn::Stmt::Expr(expr, _) = stmt {
+            router(expr, &mut parts)?;
+            let mut fallbacks = vec![];
+            fallback(expr, &mut fallbacks);
+            if let Some(ident) = fallbacks.get(1) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "only one fallback can be set per router",
+                ));
+            }
+        }
+    }
+
+    let prefix = args
+        .prefix
+        .as_ref()
+        .map(|prefix| prefix.value().trim_end_matches("/").to_string())
+        .unwrap_or_default();
+
+    let mut handlers: Vec<(String, String)> = vec![];
+    let mut helpers = vec![];
+    for (s, _, handler) in &parts {
+        let Some(handler) = handler else {
+            continue;
+        };
+        let name = helper_name(handler);
+        let full_name = handler_segments(handler).join("::");
+        match handlers.iter().find(|(helper, _)| *helper == name) {
+            Some((_, existing)) if *existing == full_name => continue,
+            Some((_, existing)) => {
+                return Err(syn::Error::new_spanned(
+                    handler,
+                    format!(
+                        "{} and {} both need a {}_path helper, rename one of them",
+                        existing, full_name, name
+                    ),
+                ))
+            }
+            None => handlers.push((name.clone(), full_name)),
+        }
+        let ident = Ident::new(&name, Span::call_site());
+        helpers.push(path_helper(&ident, &join_path(&prefix, s)));
+    }
+    let routes_meta = parts.iter().map(|(s, method, handler)| {
+        let method = method.to_string().to_uppercase();
+        let path = join_path(&prefix, s);
+        let handler = handler
+            .iter()
+            .flat_map(|handler| handler.segments.iter())
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::");
+
+        quote! { ryde::Route { method: #method, path: #path, handler: #handler } }
     });
 
-    let helpers = parts
-        .iter()
-        .map(|(s, handler)| {
-            let ident = Ident::new(&format!("{}_path", handler), Span::call_site());
-            let format_string = s
-                .split("/")
-                .map(|x| match x.starts_with(":") {
-                    true => "{}",
-                    false => x,
-                })
-                .collect::<Vec<_>>()
-                .join("/");
-            let fn_args = s
-                .split("/")
-                .filter(|x| x.starts_with(":"))
-                .map(|s| {
-                    let ident = Ident::new(&s.replace(":", ""), Span::call_site());
-
-                    quote! { #ident: impl std::fmt::Display }
-                })
-                .collect::<Vec<_>>();
-            let format_args = s
-                .split("/")
-                .filter(|x| x.starts_with(":"))
-                .map(|s| Ident::new(&s.replace(":", ""), Span::call_site()))
-                .collect::<Vec<_>>();
+    let fn_name = input.sig.ident.to_string();
+    let routes_ident = Ident::new(
+        &format!("{}_ROUTES", fn_name.to_uppercase()),
+        input.sig.ident.span(),
+    );
+
+    let input = match prefix.is_empty() {
+        true => quote! { #input },
+        false => {
+            let ItemFn {
+                attrs,
+                vis,
+                sig,
+                block,
+            } = input;
 
             quote! {
-                fn #ident(#(#fn_args,)*) -> String {
-                    format!(#format_string, #(#format_args,)*)
+                #(#attrs)*
+                #vis #sig {
+                    let router = #block;
+
+                    axum::Router::new().nest(#prefix, router)
                 }
             }
-        })
-        .collect::<Vec<_>>();
+        }
+    };
 
     Ok(quote! {
         #input
 
         #(#helpers)*
+
+        #[allow(dead_code)]
+        const #routes_ident: &[ryde::Route] = &[#(#routes_meta,)*];
     })
 }
 
-fn router(expr: &Box<Expr>, lit_str: Option<&LitStr>, output: &mut Vec<(String, Ident)>) {
-    match &**expr {
+fn normalize_routes(expr: &mut Expr, strip: Option<bool>) {
+    if let Expr::MethodCall(ExprMethodCall {
+        receiver,
+        method,
+        args,
+        ..
+    }) = expr
+    {
+        normalize_routes(receiver, strip);
+        if method == "route" || method == "nest" {
+            if let Some(Expr::Lit(ExprLit {
+                lit: Lit::Str(lit_str),
+                ..
+            })) = args.first_mut()
+            {
+                let mut path = axum_path(&lit_str.value());
+                if let Some(strip) = strip {
+                    path = normalize_path(&path, strip);
+                }
+                *lit_str = LitStr::new(&path, lit_str.span());
+            }
+        }
+        if method == "nest" {
+            if let Some(nested) = args.iter_mut().nth(1) {
+                normalize_routes(nested, strip);
+            }
+        }
+        if method == "merge" {
+            if let Some(merged) = args.first_mut() {
+                normalize_routes(merged, strip);
+            }
+        }
+    }
+}
+
+fn axum_path(path: &str) -> String {
+    path.split("/")
+        .map(|segment| match segment.starts_with("{{") {
+            true => segment.to_string(),
+            false => match segment.strip_prefix("{").and_then(|s| s.strip_suffix("}")) {
+                Some(name) if name.starts_with("*") => name.to_string(),
+                Some(name) => format!(":{}", name),
+                None => segment.to_string(),
+            },
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn normalize_path(path: &str, strip: bool) -> String {
+    if path.is_empty() {
+        return path.into();
+    }
+    let segments = path
+        .split("/")
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let mut output = format!("/{}", segments.join("/"));
+    if path.ends_with("/") && !strip && !segments.is_empty() {
+        output.push('/');
+    }
+
+    output
+}
+
+fn join_path(prefix: &str, path: &str) -> String {
+    let prefix = prefix.trim_end_matches("/");
+
+    match path.trim_start_matches("/") {
+        "" => match prefix.is_empty() {
+            true => "/".into(),
+            false => prefix.into(),
+        },
+        path => format!("{}/{}", prefix, path),
+    }
+}
+
+fn handler_segments(handler: &syn::Path) -> Vec<String> {
+    handler
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .filter(|segment| !matches!(segment.as_str(), "crate" | "self" | "super"))
+        .collect()
+}
+
+fn helper_name(handler: &syn::Path) -> String {
+    handler_segments(handler).join("_")
+}
+
+fn path_helper(handler: &Ident, path: &str) -> TokenStream {
+    let ident = Ident::new(&format!("{}_path", handler), Span::call_site());
+    let format_string = path
+        .split("/")
+        .map(|x| match path_param(x) {
+            Some(_) => "{}".into(),
+            None => x.replace("{", "{{").replace("}", "}}"),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    let fn_args = path
+        .split("/")
+        .filter_map(path_param)
+        .map(|s| {
+            let ident = Ident::new(s, Span::call_site());
+
+            quote! { #ident: impl std::fmt::Display }
+        })
+        .collect::<Vec<_>>();
+    let format_args = path
+        .split("/")
+        .filter_map(|x| Some((path_param(x)?, is_wildcard(x))))
+        .map(|(s, wildcard)| {
+            let ident = Ident::new(s, Span::call_site());
+
+            match wildcard {
+                true => quote! { ryde::percent_encode_path(&#ident.to_string()) },
+                false => quote! { ryde::percent_encode(#ident.to_string()) },
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let named_ident = Ident::new(&format!("{}_path_named", handler), Span::call_site());
+    let params_ident = Ident::new(
+        &format!("{}_PATH_PARAMS", handler.to_string().to_uppercase()),
+        Span::call_site(),
+    );
+    let names = path.split("/").filter_map(path_param).collect::<Vec<_>>();
+    let named_args = path
+        .split("/")
+        .filter_map(|x| Some((path_param(x)?, is_wildcard(x))))
+        .map(|(name, wildcard)| match wildcard {
+            true => quote! { ryde::percent_encode_path(param(#name)) },
+            false => quote! { ryde::percent_encode(param(#name)) },
+        })
+        .collect::<Vec<_>>();
+
+    quote! {
+        fn #ident(#(#fn_args,)*) -> String {
+            format!(#format_string, #(#format_args,)*)
+        }
+
+        #[allow(dead_code)]
+        const #params_ident: &[&str] = &[#(#names,)*];
+
+        #[allow(dead_code)]
+        fn #named_ident(params: &[(&str, Vec<String>)]) -> String {
+            let param = |name: &str| {
+                params
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .and_then(|(_, values)| values.first())
+                    .map(|value| value.as_str())
+                    .unwrap_or_default()
+            };
+            let query = params
+                .iter()
+                .filter(|(key, _)| ![#(#names,)*].contains(key))
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut url = format!(#format_string, #(#named_args,)*);
+            url.push_str(&ryde::query_string(&query));
+            url
+        }
+    }
+}
+
+fn path_param(segment: &str) -> Option<&str> {
+    match segment
+        .strip_prefix(":")
+        .or_else(|| segment.strip_prefix("*"))
+    {
+        Some(name) => Some(name),
+        None if segment.starts_with("{{") => None,
+        None => {
+            let name = segment.strip_prefix("{")?.strip_suffix("}")?;
+            Some(name.strip_prefix("*").unwrap_or(name))
+        }
+    }
+}
+
+fn is_wildcard(segment: &str) -> bool {
+    segment.starts_with("*") || segment.starts_with("{*")
+}
+
+fn validate_path(lit_str: &LitStr) -> Result<()> {
+    let path = lit_str.value();
+    let mut names: HashSet<&str> = HashSet::new();
+
+    if let Some(segment) = path
+        .split("/")
+        .rev()
+        .skip(1)
+        .find(|segment| is_wildcard(segment))
+    {
+        return Err(syn::Error::new_spanned(
+            lit_str,
+            format!("{} can only be the last segment of {}", segment, path),
+        ));
+    }
+
+    for name in path.split("/").filter_map(path_param) {
+        if name.is_empty() {
+            return Err(syn::Error::new_spanned(
+                lit_str,
+                format!("{} has a path param without a name", path),
+            ));
+        }
+        if syn::parse_str::<Ident>(name).is_err() {
+            return Err(syn::Error::new_spanned(
+                lit_str,
+                format!("{} is not a valid path param name", name),
+            ));
+        }
+        if !names.insert(name) {
+            return Err(syn::Error::new_spanned(
+                lit_str,
+                format!("{} is used twice in {}", name, path),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn router(expr: &Expr, output: &mut Vec<(String, Ident, Option<syn::Path>)>) -> Result<()> {
+    match expr {
         Expr::MethodCall(ExprMethodCall {
             receiver,
             method,
             args,
             ..
         }) => {
+            router(receiver, output)?;
             // 1. look for LitStr route
             match method.to_string().as_str() {
-                "route" => {
-                    match args.iter().collect::<Vec<_>>()[..] {
-                        [Expr::Lit(ExprLit {
-                            lit: Lit::Str(lit_str),
-                            ..
-                        }), Expr::Call(ExprCall { func, args, .. })] => {
-                            match &**func {
-                                Expr::Path(ExprPath { path, .. }) => {
-                                    let ident = path.get_ident();
-                                    // 2. look for get, post, put, delete, patch, trace, head
-                                    match find_route(ident, args, lit_str) {
-                                        Some(route) => output.push(route),
-                                        None => {}
-                                    }
-                                }
-                                _ => unimplemented!(),
+                "route" => match args.iter().collect::<Vec<_>>()[..] {
+                    [Expr::Lit(ExprLit {
+                        lit: Lit::Str(lit_str),
+                        ..
+                    }), method_router] => {
+                        validate_path(lit_str)?;
+                        let mut routes: Vec<(Ident, Option<syn::Path>)> = vec![];
+                        method_routes(method_router, &mut routes)?;
+                        let mut methods: HashSet<String> = HashSet::new();
+                        for (method, handler) in routes {
+                            if !methods.insert(method.to_string()) {
+                                return Err(syn::Error::new_spanned(
+                                    &method,
+                                    format!("{} is listed twice for {}", method, lit_str.value()),
+                                ));
                             }
+                            push_route(output, (lit_str.value(), method, handler))?;
                         }
-                        [Expr::Lit(ExprLit {
-                            lit: Lit::Str(lit_str),
-                            ..
-                        }), Expr::MethodCall(ExprMethodCall {
-                            receiver,
-                            method,
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
                             args,
-                            ..
-                        })] => {
-                            match find_route(Some(method), args, lit_str) {
-                                Some(route) => {
-                                    output.push(route);
-                                }
-                                None => {}
-                            };
-                            router(receiver, Some(lit_str), output);
+                            "route needs a string literal path and a method router",
+                        ))
+                    }
+                },
+                "nest" => {
+                    if let [Expr::Lit(ExprLit {
+                        lit: Lit::Str(lit_str),
+                        ..
+                    }), nested @ Expr::MethodCall(_)] = args.iter().collect::<Vec<_>>()[..]
+                    {
+                        validate_path(lit_str)?;
+                        let mut nested_parts = vec![];
+                        router(nested, &mut nested_parts)?;
+                        for (path, method, handler) in nested_parts {
+                            push_route(
+                                output,
+                                (join_path(&lit_str.value(), &path), method, handler),
+                            )?;
+                        }
+                    }
+                }
+                "merge" => {
+                    if let [merged @ Expr::MethodCall(_)] = args.iter().collect::<Vec<_>>()[..] {
+                        let mut merged_parts = vec![];
+                        router(merged, &mut merged_parts)?;
+                        for route in merged_parts {
+                            push_route(output, route)?;
                         }
-                        _ => todo!(),
                     }
                 }
                 _ => {}
             }
-            router(receiver, lit_str, output);
+
+            Ok(())
+        }
+        Expr::Call(ExprCall { func, .. }) => match &**func {
+            Expr::Path(_) => Ok(()),
+            _ => Err(syn::Error::new_spanned(
+                func,
+                "Only Router::new()... supported for now",
+            )),
+        },
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "Only Router::new()... supported for now",
+        )),
+    }
+}
+
+fn path_shape(path: &str) -> Vec<&str> {
+    path.split("/")
+        .map(|segment| match path_param(segment) {
+            Some(_) if is_wildcard(segment) => "*",
+            Some(_) => ":",
+            None => segment,
+        })
+        .collect()
+}
+
+fn push_route(
+    output: &mut Vec<(String, Ident, Option<syn::Path>)>,
+    route: (String, Ident, Option<syn::Path>),
+) -> Result<()> {
+    let (path, method, _) = &route;
+    if output.iter().any(|(p, m, _)| p == path && m == method) {
+        return Err(syn::Error::new_spanned(
+            method,
+            format!("{} {} is already routed", method, path),
+        ));
+    }
+    if let Some((p, _, _)) = output
+        .iter()
+        .find(|(p, _, _)| p != path && path_shape(p) == path_shape(path))
+    {
+        return Err(syn::Error::new_spanned(
+            method,
+            format!("{} conflicts with {}, use the same param names", path, p),
+        ));
+    }
+    output.push(route);
+
+    Ok(())
+}
+
+fn fallback<'a>(expr: &'a Expr, output: &mut Vec<&'a Ident>) {
+    if let Expr::MethodCall(ExprMethodCall {
+        receiver, method, ..
+    }) = expr
+    {
+        fallback(receiver, output);
+        if method == "fallback" {
+            output.push(method);
         }
+    }
+}
+
+fn method_routes(expr: &Expr, output: &mut Vec<(Ident, Option<syn::Path>)>) -> Result<()> {
+    // 2. look for get, post, put, delete, patch, trace, head, options, any, on
+    match expr {
         Expr::Call(ExprCall { func, args, .. }) => match &**func {
             Expr::Path(ExprPath { path, .. }) => {
-                let ident = path.get_ident();
-                match lit_str {
-                    Some(lit_str) => match find_route(ident, args, lit_str) {
-                        Some(route) => output.push(route),
-                        None => {}
-                    },
-                    None => {}
+                if let Some(segment) = path.segments.last() {
+                    output.extend(find_route(&segment.ident, args)?);
                 }
             }
-            _ => unimplemented!(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    func,
+                    "expected a method router like get(handler)",
+                ))
+            }
         },
-        _ => unimplemented!("Only Router::new()... supported for now"),
+        Expr::MethodCall(ExprMethodCall {
+            receiver,
+            method,
+            args,
+            ..
+        }) => {
+            method_routes(receiver, output)?;
+            output.extend(find_route(method, args)?);
+        }
+        _ => {}
     }
+
+    Ok(())
 }
 
 fn find_route(
-    ident: Option<&Ident>,
+    ident: &Ident,
     args: &Punctuated<Expr, syn::token::Comma>,
-    lit_str: &LitStr,
-) -> Option<(String, Ident)> {
-    match ident {
-        Some(ident) => match ident.to_string().as_str() {
-            "get" | "post" | "put" | "patch" | "delete" | "trace" | "head" => {
-                // 3. finally get name of handler ident (only idents are supported)
-                match args.last() {
-                    Some(&Expr::Path(ExprPath { ref path, .. })) => match path.get_ident() {
-                        Some(ident) => Some((lit_str.value(), ident.clone())),
-                        None => unimplemented!("Only fn handlers are supported."),
-                    },
-                    Some(_) | None => unimplemented!("Only fn handlers are supported."),
+) -> Result<Vec<(Ident, Option<syn::Path>)>> {
+    let methods = match ident.to_string().as_str() {
+        "get" | "post" | "put" | "patch" | "delete" | "trace" | "head" | "options" | "any" => {
+            vec![ident.clone()]
+        }
+        "on" => match args.first() {
+            Some(filter) if args.len() > 1 => {
+                method_filters(filter).unwrap_or_else(|| vec![Ident::new("any", filter.span())])
+            }
+            _ => vec![ident.clone()],
+        },
+        "on_method" => match args.first() {
+            Some(Expr::Lit(ExprLit {
+                lit: Lit::Str(lit_str),
+                ..
+            })) if args.len() > 1 => match syn::parse_str::<Ident>(&lit_str.value()) {
+                Ok(method) => vec![Ident::new(&method.to_string(), lit_str.span())],
+                Err(_) => {
+                    return Err(syn::Error::new_spanned(
+                        lit_str,
+                        format!("{} is not a supported method name", lit_str.value()),
+                    ))
                 }
+            },
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "on_method needs a method name like on_method(\"QUERY\", handler)",
+                ))
             }
-            _ => None,
         },
-        None => todo!("Failed when looking for http method ident"),
+        _ => return Ok(vec![]),
+    };
+
+    // 3. finally get the handler path (closures are routed without url helpers)
+    let handler = match args.last() {
+        Some(Expr::Path(ExprPath { path, .. })) => Some(path.clone()),
+        Some(Expr::Closure(_)) => None,
+        Some(expr) => {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "Only fn and closure handlers are supported.",
+            ))
+        }
+        None => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("{} needs a handler", ident),
+            ))
+        }
+    };
+
+    Ok(methods
+        .into_iter()
+        .map(|method| (method, handler.clone()))
+        .collect())
+}
+
+const METHOD_FILTERS: &[&str] = &[
+    "DELETE", "GET", "HEAD", "OPTIONS", "PATCH", "POST", "PUT", "TRACE",
+];
+
+fn method_filters(expr: &Expr) -> Option<Vec<Ident>> {
+    match expr {
+        Expr::Path(ExprPath { path, .. }) => {
+            let mut segments = path.segments.iter().rev();
+            let method = segments.next()?;
+            let filter = segments.next()?;
+            match filter.ident == "MethodFilter"
+                && METHOD_FILTERS.contains(&method.ident.to_string().as_str())
+            {
+                true => Some(vec![Ident::new(
+                    &method.ident.to_string().to_lowercase(),
+                    method.ident.span(),
+                )]),
+                false => None,
+            }
+        }
+        Expr::MethodCall(ExprMethodCall {
+            receiver,
+            method,
+            args,
+            ..
+        }) if method == "or" => {
+            let mut methods = method_filters(receiver)?;
+            for arg in args {
+                methods.extend(method_filters(arg)?);
+            }
+
+            Some(methods)
+        }
+        _ => None,
     }
 }
 
@@ -163,10 +607,22 @@ pub fn routes_macro(input: StateRouter) -> Result<TokenStream> {
         })
         .collect();
 
+    for (lit, _handlers, _expr) in &parts {
+        match lit {
+            Lit::Str(lit_str) => validate_path(lit_str)?,
+            _ => return Err(syn::Error::new_spanned(lit, "route needs to be a string")),
+        }
+    }
+
     let routes = parts.iter().map(|(lit, _ident, expr)| {
+        let lit = match lit {
+            Lit::Str(lit_str) => LitStr::new(&axum_path(&lit_str.value()), lit_str.span()),
+            _ => unreachable!(),
+        };
         quote! { .route(#lit, #expr) }
     });
 
+    let mut seen: HashSet<String> = HashSet::new();
     let helpers = parts
         .iter()
         .flat_map(|(lit, handlers, _expr)| {
@@ -175,40 +631,16 @@ pub fn routes_macro(input: StateRouter) -> Result<TokenStream> {
                 .map(|handler| handler.to_string())
                 .collect::<HashSet<_>>()
                 .into_iter()
+                .filter(|x| seen.insert(x.clone()))
+                .collect::<Vec<_>>()
+                .into_iter()
                 .map(|x| {
-                    let ident = Ident::new(&format!("{}_path", x), Span::call_site());
                     let s = match lit {
                         syn::Lit::Str(s) => s.value(),
-                        _ => panic!("route needs to a string"),
+                        _ => unreachable!(),
                     };
-                    let format_string = s
-                        .split("/")
-                        .map(|x| match x.starts_with(":") {
-                            true => "{}",
-                            false => x,
-                        })
-                        .collect::<Vec<_>>()
-                        .join("/");
-                    let fn_args = s
-                        .split("/")
-                        .filter(|x| x.starts_with(":"))
-                        .map(|s| {
-                            let ident = Ident::new(&s.replace(":", ""), Span::call_site());
-
-                            quote! { #ident: impl std::fmt::Display }
-                        })
-                        .collect::<Vec<_>>();
-                    let format_args = s
-                        .split("/")
-                        .filter(|x| x.starts_with(":"))
-                        .map(|s| Ident::new(&s.replace(":", ""), Span::call_site()))
-                        .collect::<Vec<_>>();
-
-                    quote! {
-                        fn #ident(#(#fn_args,)*) -> String {
-                            format!(#format_string, #(#format_args,)*)
-                        }
-                    }
+
+                    path_helper(&Ident::new(&x, Span::call_site()), &s)
                 })
                 .collect::<Vec<_>>()
         })
@@ -221,7 +653,9 @@ pub fn routes_macro(input: StateRouter) -> Result<TokenStream> {
 
     let tokens = quote! {
         fn routes() -> axum::Router<#generic> {
-            use axum::routing::{get, post, put, patch, head, trace};
+            use axum::routing::{
+                any, delete, get, head, on, options, patch, post, put, trace, MethodFilter,
+            };
 
             axum::Router::new()#(#routes)*
         }
@@ -256,33 +690,87 @@ fn handlers(expr: &Expr) -> Vec<Ident> {
 }
 
 fn handler(args: &Punctuated<Expr, syn::token::Comma>) -> Vec<Ident> {
-    args.iter()
+    args.last()
+        .into_iter()
         .filter_map(|arg| match arg {
-            Expr::Path(ExprPath { path, .. }) => path.get_ident().cloned(),
+            Expr::Path(ExprPath { path, .. }) => {
+                Some(Ident::new(&helper_name(path), Span::call_site()))
+            }
             _ => None,
         })
         .collect::<Vec<Ident>>()
 }
 
 pub fn url_macro(Url { url, path }: Url) -> Result<TokenStream> {
-    let fn_name = Ident::new(&format!("{}_path", url.to_string()), Span::call_site());
+    let fn_name = Ident::new(&format!("{}_path", helper_name(&url)), url.span());
+    let (query, path): (Vec<Expr>, Vec<Expr>) = path
+        .into_iter()
+        .partition(|expr| matches!(expr, Expr::Assign(_)));
+
+    if query.is_empty() {
+        return Ok(quote! {
+            {
+                let _ = &#url;
+                #fn_name(#(#path,)*)
+            }
+        });
+    }
+
+    let mut keys = vec![];
+    let query = query
+        .iter()
+        .map(|expr| match expr {
+            Expr::Assign(ExprAssign { left, right, .. }) => match &**left {
+                Expr::Path(ExprPath { path, .. }) if path.get_ident().is_some() => {
+                    let key = path.get_ident().unwrap().to_string();
+                    keys.push(key.clone());
+                    Ok(quote! { (#key, ryde::ToQueryValue::to_query_values(&(#right))) })
+                }
+                _ => Err(syn::Error::new_spanned(
+                    left,
+                    "query params should look like url!(handler, name = value)",
+                )),
+            },
+            _ => unreachable!(),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if path.is_empty() {
+        let named_fn = Ident::new(&format!("{}_path_named", helper_name(&url)), url.span());
+        let params = Ident::new(
+            &format!("{}_PATH_PARAMS", helper_name(&url).to_uppercase()),
+            url.span(),
+        );
+        let message = format!(
+            "url!({}) needs a value for each of its path params",
+            handler_segments(&url).join("::")
+        );
+
+        return Ok(quote! {
+            {
+                const _: () = assert!(ryde::has_path_params(#params, &[#(#keys,)*]), #message);
+                let _ = &#url;
+                #named_fn(&[#(#query,)*])
+            }
+        });
+    }
 
     Ok(quote! {
         {
             let _ = &#url;
-            #fn_name(#path)
+            format!("{}{}", #fn_name(#(#path,)*), ryde::query_string(&[#(#query,)*]))
         }
     })
 }
 
 pub struct Url {
-    url: Ident,
+    url: syn::Path,
     path: Punctuated<Expr, Token![,]>,
 }
 
 impl Parse for Url {
     fn parse(input: syn::parse::ParseStream) -> Result<Self> {
-        let url = input.parse::<syn::Ident>()?;
+        let url = input.parse::<syn::Path>()?;
         let _comma: Option<Token![,]> = input.parse()?;
         let path = Punctuated::parse_terminated(input)?;
 
@@ -290,18 +778,111 @@ impl Parse for Url {
     }
 }
 
+const ROUTER_ARGS: &[&str] = &["prefix", "trailing_slash"];
+
+fn closest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    fn distance(a: &str, b: &str) -> usize {
+        let b = b.chars().collect::<Vec<_>>();
+        let mut row = (0..=b.len()).collect::<Vec<_>>();
+        for (i, a) in a.chars().enumerate() {
+            let mut previous = row[0];
+            row[0] = i + 1;
+            for (j, b) in b.iter().enumerate() {
+                let current = row[j + 1];
+                row[j + 1] = match a == *b {
+                    true => previous,
+                    false => 1 + previous.min(row[j]).min(current),
+                };
+                previous = current;
+            }
+        }
+        row[b.len()]
+    }
+
+    candidates
+        .iter()
+        .map(|candidate| (distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 3)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+#[derive(Default)]
+pub struct RouterArgs {
+    prefix: Option<LitStr>,
+    strip_trailing_slash: Option<bool>,
+}
+
+impl Parse for RouterArgs {
+    fn parse(input: syn::parse::ParseStream) -> Result<Self> {
+        let mut args = RouterArgs::default();
+        let metas = Punctuated::<MetaNameValue, Token![,]>::parse_terminated(input)?;
+
+        for meta in metas {
+            match meta
+                .path
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .as_deref()
+            {
+                Some("prefix") => match meta.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(lit_str),
+                        ..
+                    }) => args.prefix = Some(lit_str),
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "prefix should be a string like \"/api\"",
+                        ))
+                    }
+                },
+                Some("trailing_slash") => match &meta.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(lit_str),
+                        ..
+                    }) if matches!(lit_str.value().as_str(), "strip" | "keep") => {
+                        args.strip_trailing_slash = Some(lit_str.value() == "strip")
+                    }
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "trailing_slash should be \"strip\" or \"keep\"",
+                        ))
+                    }
+                },
+                name => {
+                    let message = match name.and_then(|name| closest(name, ROUTER_ARGS)) {
+                        Some(arg) => format!("unknown router argument, did you mean {}?", arg),
+                        None => format!(
+                            "unknown router argument, expected one of {}",
+                            ROUTER_ARGS.join(", ")
+                        ),
+                    };
+                    return Err(syn::Error::new_spanned(meta.path, message));
+                }
+            }
+        }
+
+        Ok(args)
+    }
+}
+
 pub struct StateRouter {
     routes: Punctuated<ExprTuple, Token![,]>,
-    state: Option<syn::TypePath>,
+    state: Option<syn::Type>,
 }
 
 impl Parse for StateRouter {
     fn parse(input: syn::parse::ParseStream) -> Result<Self> {
         let routes = Punctuated::parse_separated_nonempty(input)?;
 
-        let state = match input.parse::<syn::Ident>().ok() {
-            Some(_) => input.parse::<syn::TypePath>().ok(),
-            None => None,
+        let state = match input.is_empty() {
+            true => None,
+            false => {
+                input.parse::<syn::Ident>()?;
+                Some(input.parse::<syn::Type>()?)
+            }
         };
 
         Ok(Self { state, routes })
@@ -1,53 +1,104 @@
-use std::path::MAIN_SEPARATOR_STR;
+use std::path::{Path, MAIN_SEPARATOR_STR};
 
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{Data, DeriveInput, Ident, LitStr, Result};
+use syn::{
+    parse::Parse, punctuated::Punctuated, Data, DeriveInput, Expr, ExprLit, Ident, Lit, LitInt,
+    LitStr, MetaNameValue, Result, Token,
+};
 
 pub fn static_files_macro(input: DeriveInput) -> Result<TokenStream> {
     let struct_ident = input.ident;
     let Data::Struct(_) = input.data else {
-        panic!("Only structs are supported");
+        return Err(syn::Error::new_spanned(
+            struct_ident,
+            "Only structs are supported",
+        ));
     };
-    let Some(path) = input
+    let args = |name: &str| {
+        input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident(name))
+            .map(|attr| attr.parse_args::<FolderArgs>())
+            .last()
+            .transpose()
+    };
+    let (FolderArgs { folder, max_age }, single) = match (args("folder")?, args("file")?) {
+        (Some(args), _) => (args, false),
+        (None, Some(args)) => (args, true),
+        (None, None) => return Ok(quote! {}),
+    };
+    let max_age = match max_age {
+        Some(max_age) => max_age.base10_parse::<u64>()?,
+        None => 0,
+    };
+    let mime = input
         .attrs
         .iter()
-        .filter(|attr| attr.path().is_ident("folder"))
-        .filter_map(|attr| attr.parse_args::<LitStr>().ok())
-        .last()
-    else {
-        return Ok(quote! {});
-    };
-    let Some(prefix) = input
+        .filter(|attr| attr.path().is_ident("mime"))
+        .map(|attr| attr.parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .map(|meta| match (meta.path.get_ident(), &meta.value) {
+            (
+                Some(ext),
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(content_type),
+                    ..
+                }),
+            ) => Ok((ext.to_string(), content_type.value())),
+            _ => Err(syn::Error::new_spanned(
+                &meta,
+                "mime overrides look like #[mime(json = \"application/json\")]",
+            )),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let prefix = input
         .attrs
         .iter()
         .filter(|attr| attr.path().is_ident("prefix"))
         .filter_map(|attr| attr.parse_args::<LitStr>().ok())
-        .last()
-    else {
-        return Ok(quote! {});
+        .last();
+    let prefix = match (prefix, single) {
+        (Some(prefix), _) => prefix,
+        (None, true) => LitStr::new("/", Span::call_site()),
+        (None, false) => return Ok(quote! {}),
     };
-    let path = std::env::current_dir().unwrap().join(path.value());
-    let p1 = path.clone();
-    let root_str = p1.to_string_lossy();
-    let files = std::fs::read_dir(path)
-        .unwrap()
-        .into_iter()
-        .filter_map(|dir_entry| dir_entry.ok())
-        .filter(|file| match file.file_type() {
-            Ok(file_type) => file_type.is_file(),
-            Err(_) => false,
-        })
-        .map(|dir_entry| dir_entry.path())
-        .collect::<Vec<_>>();
+    let path = std::env::current_dir().unwrap().join(folder.value());
+    let (root, files) = match single {
+        true => {
+            if !path.is_file() {
+                return Err(syn::Error::new_spanned(
+                    &folder,
+                    format!("could not read file {}", folder.value()),
+                ));
+            }
+            (path.parent().unwrap().to_path_buf(), vec![path])
+        }
+        false => {
+            let files = std::fs::read_dir(&path)
+                .map_err(|err| {
+                    syn::Error::new_spanned(
+                        &folder,
+                        format!("could not read folder {}: {}", folder.value(), err),
+                    )
+                })?
+                .into_iter()
+                .filter_map(|dir_entry| dir_entry.ok())
+                .filter(|file| match file.file_type() {
+                    Ok(file_type) => file_type.is_file(),
+                    Err(_) => false,
+                })
+                .map(|dir_entry| dir_entry.path())
+                .collect::<Vec<_>>();
+            (path, files)
+        }
+    };
+    let root_str = root.to_string_lossy();
     let consts = files.iter().map(|path| {
-        let ident_name = path
-            .clone()
-            .with_extension("")
-            .file_name()
-            .unwrap()
-            .to_string_lossy()
-            .to_uppercase();
+        let ident_name = const_name(path);
         let bytes_ident = Ident::new(&format!("{}_BYTES", &ident_name), Span::call_site());
         let hash_ident = Ident::new(&format!("{}_HASH", &ident_name), Span::call_site());
         let filename = path.file_name().unwrap().to_string_lossy();
@@ -63,7 +114,7 @@ pub fn static_files_macro(input: DeriveInput) -> Result<TokenStream> {
     };
     let rendered = files.iter().map(|path| {
         let f1 = path.clone();
-        let ident_name = path.clone().with_extension("").file_name().unwrap().to_string_lossy().to_uppercase();
+        let ident_name = const_name(path);
         let hash_ident = Ident::new(&format!("{}_HASH", &ident_name), Span::call_site());
         let filename = f1.file_name().unwrap().to_string_lossy();
         let filename = format!("{}{}", pfx, filename);
@@ -71,10 +122,10 @@ pub fn static_files_macro(input: DeriveInput) -> Result<TokenStream> {
             if let Some(ext) = ext.to_str() {
                 match ext {
                     "js" => quote! {
-                        <script src={format!("{}?v={}", #filename, Self::#hash_ident)} defer></script>
+                        <script src={ryde::fingerprint_path(#filename, Self::#hash_ident)} defer></script>
                     },
                     "css" => quote! {
-                        <link rel="stylesheet" href={format!("{}?v={}", #filename, Self::#hash_ident)} />
+                        <link rel="stylesheet" href={ryde::fingerprint_path(#filename, Self::#hash_ident)} />
                     },
                     _ => quote! {}
                 }
@@ -86,27 +137,15 @@ pub fn static_files_macro(input: DeriveInput) -> Result<TokenStream> {
         }
     });
     let get_matches = files.iter().map(|path| {
-        let ident_name = path
-            .clone()
-            .with_extension("")
-            .file_name()
-            .unwrap()
-            .to_string_lossy()
-            .to_uppercase();
+        let ident_name = const_name(path);
         let bytes_ident = Ident::new(&format!("{}_BYTES", &ident_name), Span::call_site());
-        let content_type = if let Some(ext) = path.extension() {
-            if let Some(ext) = ext.to_str() {
-                match ext {
-                    "js" => "text/javascript",
-                    "css" => "text/css",
-                    "wasm" => "application/wasm",
-                    _ => "application/octect-stream",
-                }
-            } else {
-                "application/octet-stream"
-            }
-        } else {
-            "application/octet-stream"
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        let content_type = match mime.iter().find(|(name, _)| name == ext) {
+            Some((_, content_type)) => content_type.as_str(),
+            None => content_type(ext),
         };
         let filename = format!(
             "{}{}",
@@ -120,8 +159,121 @@ pub fn static_files_macro(input: DeriveInput) -> Result<TokenStream> {
         }
     });
 
+    let encoded_matches = files.iter().filter_map(|path| {
+        let encoding = match path.extension()?.to_str()? {
+            "br" => "br",
+            "gz" => "gzip",
+            _ => return None,
+        };
+        let original = path.with_extension("");
+        if !files.contains(&original) {
+            return None;
+        }
+        let ident_name = const_name(path);
+        let bytes_ident = Ident::new(&format!("{}_BYTES", &ident_name), Span::call_site());
+        let filename = format!(
+            "{}{}",
+            &pfx_value,
+            original.file_name().unwrap().to_string_lossy()
+        );
+        Some(quote! {
+            (#filename, #encoding) => {
+                Some(Self::#bytes_ident)
+            }
+        })
+    });
+
+    let etag_matches = files.iter().map(|path| {
+        let ident_name = const_name(path);
+        let hash_ident = Ident::new(&format!("{}_HASH", &ident_name), Span::call_site());
+        let filename = format!(
+            "{}{}",
+            &pfx_value,
+            path.file_name().unwrap().to_string_lossy()
+        );
+        quote! {
+            #filename => {
+                Some(format!("\"{:x}\"", Self::#hash_ident))
+            }
+        }
+    });
+
+    let disk_matches = files.iter().map(|path| {
+        let filename = format!(
+            "{}{}",
+            &pfx_value,
+            path.file_name().unwrap().to_string_lossy()
+        );
+        let path = path.to_string_lossy();
+        quote! {
+            #filename => std::fs::read(#path).ok().map(std::borrow::Cow::Owned)
+        }
+    });
+    let dev_fs = cfg!(feature = "debug-fs");
+    let load_from_disk = match (dev_fs, single) {
+        (false, _) => quote! {},
+        (true, true) => quote! {
+            if Self::DEV_FS {
+                return match uri {
+                    #(#disk_matches,)*
+                    _ => None
+                };
+            }
+        },
+        (true, false) => quote! {
+            if Self::DEV_FS {
+                let name = uri.strip_prefix(#pfx_value)?;
+                if name.is_empty()
+                    || name.contains(':')
+                    || name
+                        .split(['/', '\\'])
+                        .any(|segment| matches!(segment, "" | "." | ".."))
+                {
+                    return None;
+                }
+                return std::fs::read(std::path::Path::new(#root_str).join(name))
+                    .ok()
+                    .map(std::borrow::Cow::Owned);
+            }
+        },
+    };
+    let content_types = mime
+        .iter()
+        .map(|(ext, content_type)| (ext.as_str(), content_type.as_str()))
+        .chain(
+            CONTENT_TYPES
+                .iter()
+                .filter(|(ext, _)| !mime.iter().any(|(name, _)| name == ext))
+                .copied(),
+        )
+        .map(|(ext, content_type)| quote! { #ext => #content_type });
+
+    let url_matches = files.iter().map(|path| {
+        let ident_name = const_name(path);
+        let hash_ident = Ident::new(&format!("{}_HASH", &ident_name), Span::call_site());
+        let name = path.file_name().unwrap().to_string_lossy();
+        let filename = format!("{}{}", pfx, name);
+        quote! {
+            #name => Some(ryde::fingerprint_path(#filename, Self::#hash_ident))
+        }
+    });
+
+    let keys = files.iter().map(|path| {
+        format!(
+            "{}{}",
+            &pfx_value,
+            path.file_name().unwrap().to_string_lossy()
+        )
+    });
+
     Ok(quote! {
         impl #struct_ident {
+            pub const MAX_AGE: u64 = #max_age;
+
+            pub const DEV_FS: bool = #dev_fs && cfg!(debug_assertions);
+
+            pub const FILES: &'static [&'static str] = &[#(#keys,)*];
+
             #(#consts)*
 
             pub fn get<'a, 'b>(uri: &'a str) -> Option<(&'b str, &'static [u8])> {
@@ -131,6 +283,61 @@ pub fn static_files_macro(input: DeriveInput) -> Result<TokenStream> {
                 }
             }
 
+            pub fn content_type(uri: &str) -> &'static str {
+                match uri.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default() {
+                    #(#content_types,)*
+                    _ => "application/octet-stream"
+                }
+            }
+
+            pub fn find(uri: &str) -> Option<(&'static str, std::borrow::Cow<'static, [u8]>)> {
+                if Self::DEV_FS {
+                    return Self::load(uri).map(|bytes| (Self::content_type(uri), bytes));
+                }
+                Self::get(uri).map(|(content_type, bytes)| {
+                    (content_type, std::borrow::Cow::Borrowed(bytes))
+                })
+            }
+
+            pub fn download(uri: &str) -> Option<ryde::Download> {
+                let (content_type, bytes) = Self::find(uri)?;
+                let filename = uri.rsplit('/').next().unwrap_or(uri);
+
+                Some(ryde::Download::from_bytes(filename, bytes).content_type(content_type))
+            }
+
+            pub fn load(uri: &str) -> Option<std::borrow::Cow<'static, [u8]>> {
+                #load_from_disk
+                Self::get(uri).map(|(_, bytes)| std::borrow::Cow::Borrowed(bytes))
+            }
+
+            pub fn get_encoded(uri: &str, encoding: &str) -> Option<&'static [u8]> {
+                if Self::DEV_FS {
+                    return None;
+                }
+                match (uri, encoding) {
+                    #(#encoded_matches,)*
+                    _ => None
+                }
+            }
+
+            pub fn etag(uri: &str) -> Option<String> {
+                if Self::DEV_FS {
+                    return Self::load(uri).map(|bytes| format!("\"{:x}\"", Self::hash(&bytes)));
+                }
+                match uri {
+                    #(#etag_matches,)*
+                    _ => None
+                }
+            }
+
+            pub fn url(name: &str) -> Option<String> {
+                match name {
+                    #(#url_matches,)*
+                    _ => None
+                }
+            }
+
             pub fn render() -> ryde::Component {
                 html! {
                     <>
@@ -155,3 +362,98 @@ pub fn static_files_macro(input: DeriveInput) -> Result<TokenStream> {
         }
     })
 }
+
+const CONTENT_TYPES: &[(&str, &str)] = &[
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("css", "text/css"),
+    ("wasm", "application/wasm"),
+    ("html", "text/html; charset=utf-8"),
+    ("htm", "text/html; charset=utf-8"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("xml", "application/xml"),
+    ("webmanifest", "application/manifest+json"),
+    ("pdf", "application/pdf"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("ico", "image/x-icon"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+];
+
+fn content_type(ext: &str) -> &'static str {
+    CONTENT_TYPES
+        .iter()
+        .find(|(name, _)| *name == ext)
+        .map(|(_, content_type)| *content_type)
+        .unwrap_or("application/octet-stream")
+}
+
+fn const_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect::<String>();
+    match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", name),
+        false => name,
+    }
+}
+
+struct FolderArgs {
+    folder: LitStr,
+    max_age: Option<LitInt>,
+}
+
+impl Parse for FolderArgs {
+    fn parse(input: syn::parse::ParseStream) -> Result<Self> {
+        let folder = input.parse::<LitStr>()?;
+        let mut max_age = None;
+
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            let metas = Punctuated::<MetaNameValue, Token![,]>::parse_terminated(input)?;
+            for meta in metas {
+                if !meta.path.is_ident("max_age") {
+                    return Err(syn::Error::new_spanned(
+                        meta.path,
+                        "unknown folder argument, expected max_age",
+                    ));
+                }
+                match meta.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Int(lit_int),
+                        ..
+                    }) => max_age = Some(lit_int),
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "max_age should be a number of seconds like 86400",
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(Self { folder, max_age })
+    }
+}
@@ -1,8 +1,44 @@
 pub use rusqlite;
-pub use ryde_macros::db;
+pub use ryde_macros::{db, migrations};
 pub use tokio_rusqlite::{self, Connection};
 extern crate self as ryde_db;
 
+pub async fn migrate(
+    connection: &Connection,
+    migrations: &'static [(&'static str, &'static str)],
+) -> crate::Result<Vec<&'static str>> {
+    let applied = connection
+        .call(move |conn| {
+            conn.execute_batch(
+                "create table if not exists schema_migrations (
+                    name text primary key not null,
+                    applied_at text not null default current_timestamp
+                )",
+            )?;
+            let mut applied = vec![];
+            for (name, sql) in migrations {
+                let exists = conn.query_row(
+                    "select count(*) from schema_migrations where name = ?",
+                    [name],
+                    |row| row.get::<_, i64>(0),
+                )? > 0;
+                if exists {
+                    continue;
+                }
+                let tx = conn.transaction()?;
+                tx.execute_batch(sql)?;
+                tx.execute("insert into schema_migrations (name) values (?)", [name])?;
+                tx.commit()?;
+                applied.push(*name);
+            }
+
+            Ok(applied)
+        })
+        .await?;
+
+    Ok(applied)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,6 +102,36 @@ mod tests {
                 as Post
     );
 
+    #[test]
+    async fn migrate_works() -> ryde::Result<()> {
+        const MIGRATIONS: &[(&str, &str)] = &[
+            (
+                "0001_create_notes.sql",
+                "create table notes (id integer primary key not null, body text not null)",
+            ),
+            (
+                "0002_add_notes_title.sql",
+                "alter table notes add column title text",
+            ),
+        ];
+
+        let db = db(":memory:").await?;
+        assert_eq!(
+            vec!["0001_create_notes.sql", "0002_add_notes_title.sql"],
+            db.migrate(MIGRATIONS).await?
+        );
+        assert_eq!(Vec::<&str>::new(), db.migrate(MIGRATIONS).await?);
+        assert_eq!(
+            vec!["0001_create_notes.sql", "0002_add_notes_title.sql"],
+            migrations!("examples/migrations")
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
     #[test]
     async fn it_works() -> ryde::Result<()> {
         let db = db(":memory:").await?;
@@ -9,15 +9,21 @@ pub use axum::{
     extract::*,
     http::{self, header::*, Uri},
     response::*,
-    routing::{any, delete, get, head, patch, post, put, trace},
+    routing::{any, delete, get, head, options, patch, post, put, trace},
     *,
 };
 pub use axum_extra::{extract::*, headers};
 pub use cookie::Cookie;
-pub use db::{db, rusqlite, tokio_rusqlite, Connection};
+pub use db::{db, migrate, migrations, rusqlite, tokio_rusqlite, Connection};
 pub use html::{component, escape, html, Component, Elements, Render};
-pub use router::{router, routes, url};
-pub use ryde_macros::{RequestParts, StaticFiles};
+pub use router::{
+    call, form_attrs, has_path_params, method_override, on_method, openapi, parse_route,
+    percent_decode, percent_encode, percent_encode_path, query_string, request_id, robots,
+    route_list, route_table, router, routes, sitemap, typescript, url, url_for, url_with_base,
+    with_method_override, with_request_id, Comma, Cursor, FormAttrs, MatchedRoute, Pagination,
+    RequestId, Route, ToQueryValue,
+};
+pub use ryde_macros::{download, Config, RequestParts, StaticFiles};
 pub use serde;
 pub use serde::*;
 pub use std::fmt::Display;
@@ -34,10 +40,52 @@ pub fn server(ip: &str, router: Router) -> Result<()> {
     Ok(())
 }
 
+pub const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 pub async fn serve(ip: &str, router: Router) {
     let listener = tokio::net::TcpListener::bind(ip).await.unwrap();
     println!("Listening on {}", ip);
-    axum::serve(listener, router).await.unwrap();
+    let (signalled, signal) = tokio::sync::oneshot::channel::<()>();
+    let server = std::future::IntoFuture::into_future(
+        axum::serve(listener, router).with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            let _ = signalled.send(());
+        }),
+    );
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => result.unwrap(),
+        _ = signal => match tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut server).await {
+            Ok(result) => result.unwrap(),
+            Err(_) => println!("Shutdown timed out after {:?}", SHUTDOWN_TIMEOUT),
+        },
+    }
+}
+
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for ctrl-c");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for sigterm")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    println!("Shutting down");
 }
 
 #[macro_export]
@@ -45,6 +93,9 @@ macro_rules! render_static_files {
     () => {{
         Assets::render()
     }};
+    ($assets:ident) => {{
+        $assets::render()
+    }};
 }
 
 pub type Html = Component;
@@ -56,17 +107,154 @@ impl IntoResponse for Html {
 }
 
 pub fn redirect(s: String) -> Response {
+    redirect_with(http::StatusCode::SEE_OTHER, s)
+}
+
+pub fn redirect_with(status: http::StatusCode, s: String) -> Response {
     let headers = [(SET_COOKIE, format!("flash={}", "")), (LOCATION, s.into())];
 
+    (status, headers).into_response()
+}
+
+pub fn redirect_with_flash(s: String, message: &str) -> Response {
+    let headers = [
+        (
+            SET_COOKIE,
+            format!(
+                "flash={}; Path=/; HttpOnly; SameSite=Lax",
+                percent_encode(message)
+            ),
+        ),
+        (LOCATION, s),
+    ];
+
     (http::StatusCode::SEE_OTHER, headers).into_response()
 }
 
+pub struct Download {
+    filename: String,
+    content_type: String,
+    body: body::Body,
+}
+
+impl Download {
+    pub fn from_bytes(filename: impl Into<String>, bytes: impl Into<body::Body>) -> Self {
+        Self {
+            filename: filename.into(),
+            content_type: "application/octet-stream".into(),
+            body: bytes.into(),
+        }
+    }
+
+    pub fn from_reader(
+        filename: impl Into<String>,
+        reader: impl tokio::io::AsyncRead + Send + 'static,
+    ) -> Self {
+        Self::from_bytes(
+            filename,
+            body::Body::from_stream(tokio_util::io::ReaderStream::new(reader)),
+        )
+    }
+
+    pub async fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = tokio::fs::File::open(path).await?;
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Ok(Self::from_reader(filename, file))
+    }
+
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+}
+
+impl IntoResponse for Download {
+    fn into_response(self) -> Response {
+        let disposition = format!(
+            "attachment; filename=\"{}\"",
+            self.filename.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+
+        (
+            [
+                (CONTENT_TYPE, self.content_type),
+                (CONTENT_DISPOSITION, disposition),
+            ],
+            self.body,
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Flash(pub Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Flash
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let message = parts
+            .headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().strip_prefix("flash="))
+            .find(|message| !message.is_empty())
+            .map(percent_decode);
+
+        Ok(Flash(message))
+    }
+}
+
+impl IntoResponseParts for Flash {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(
+        self,
+        mut res: ResponseParts,
+    ) -> std::result::Result<ResponseParts, Self::Error> {
+        if self.0.is_some() {
+            res.headers_mut().append(
+                SET_COOKIE,
+                http::HeaderValue::from_static("flash=; Path=/; Max-Age=0"),
+            );
+        }
+
+        Ok(res)
+    }
+}
+
 #[macro_export]
 macro_rules! redirect_to {
-    ($ident:ident $(,$expr:expr)*) => {{
-        let _ = $ident;
-        redirect(url!($ident, $($expr),*))
-    }}
+    (status = $status:expr, $($tt:tt)*) => {{
+        $crate::redirect_with($status, url!($($tt)*))
+    }};
+    (flash = $flash:expr, $($tt:tt)*) => {{
+        $crate::redirect_with_flash(url!($($tt)*), $flash)
+    }};
+    ($($tt:tt)*) => {{
+        $crate::redirect(url!($($tt)*))
+    }};
+}
+
+#[macro_export]
+macro_rules! uri {
+    ($($tt:tt)*) => {{
+        axum::http::Uri::try_from(url!($($tt)*)).expect("url! should build a valid uri")
+    }};
 }
 
 #[macro_export]
@@ -196,36 +384,271 @@ impl From<axum_extra::extract::multipart::MultipartError> for Error {
 
 #[macro_export]
 macro_rules! embed_static_files {
+    (@not_found $assets:ident, $prefix:expr) => {
+        match $assets::get(&format!("{}404.html", $prefix)) {
+            Some((content_type, bytes)) => axum::response::IntoResponse::into_response((
+                axum::http::StatusCode::NOT_FOUND,
+                [(axum::http::header::CONTENT_TYPE, content_type)],
+                bytes,
+            )),
+            None => axum::response::IntoResponse::into_response((
+                axum::http::StatusCode::NOT_FOUND,
+                [(axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+                "not found",
+            )),
+        }
+    };
+    (@not_found $assets:ident, $prefix:expr, $not_found:path) => {
+        axum::response::IntoResponse::into_response($not_found().await)
+    };
+
+    (@serve $assets:ident, $path:expr, $query:expr, $headers:ident, $prefix:expr $(, $not_found:path)?) => {{
+        let path: &str = $path;
+        let query: Option<&str> = $query;
+        let unhashed = $crate::split_fingerprint(path).filter(|_| $assets::etag(path).is_none());
+        let path: &str = unhashed.as_ref().map(|(name, _)| name.as_str()).unwrap_or(path);
+        match ($assets::find(path), $assets::etag(path)) {
+            (Some((content_type, bytes)), Some(etag)) => {
+                let fingerprinted = unhashed
+                    .iter()
+                    .map(|(_, hash)| *hash)
+                    .chain(
+                        query
+                            .into_iter()
+                            .flat_map(|query| query.split('&'))
+                            .filter_map(|pair| pair.strip_prefix("v="))
+                            .filter_map(|version| version.parse::<u64>().ok()),
+                    )
+                    .any(|version| format!("\"{:x}\"", version) == etag);
+                let accepts = |encoding: &str| {
+                    $headers
+                        .get(axum::http::header::ACCEPT_ENCODING)
+                        .and_then(|value| value.to_str().ok())
+                        .map(|value| {
+                            value.split(',').any(|part| {
+                                let mut parts = part.split(';').map(str::trim);
+                                parts.next() == Some(encoding)
+                                    && parts.all(|param| param.replace(' ', "") != "q=0")
+                            })
+                        })
+                        .unwrap_or(false)
+                };
+                let encoded = ["br", "gzip"].into_iter().find_map(|encoding| {
+                    match accepts(encoding) {
+                        true => $assets::get_encoded(path, encoding).map(|bytes| (encoding, bytes)),
+                        false => None,
+                    }
+                });
+                let (etag, bytes) = match encoded {
+                    Some((encoding, bytes)) => (
+                        format!("{}-{}\"", etag.trim_end_matches('"'), encoding),
+                        std::borrow::Cow::Borrowed(bytes),
+                    ),
+                    None => (etag, bytes),
+                };
+                let cache_control = match fingerprinted {
+                    true => "public, max-age=31536000, immutable".to_string(),
+                    false => format!("public, max-age={}", $assets::MAX_AGE),
+                };
+                let not_modified = $headers
+                    .get(axum::http::header::IF_NONE_MATCH)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| {
+                        value
+                            .split(',')
+                            .map(str::trim)
+                            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+                    })
+                    .unwrap_or(false);
+                let range_header = match encoded {
+                    Some(_) => None,
+                    None => $headers
+                        .get(axum::http::header::RANGE)
+                        .and_then(|value| value.to_str().ok()),
+                };
+                let mut response = if not_modified {
+                    axum::response::IntoResponse::into_response((
+                        axum::http::StatusCode::NOT_MODIFIED,
+                        [
+                            (axum::http::header::ETAG, etag),
+                            (axum::http::header::CACHE_CONTROL, cache_control),
+                            (axum::http::header::VARY, "accept-encoding".to_string()),
+                        ],
+                    ))
+                } else if range_header
+                    .map(|value| $crate::byte_range_unsatisfiable(value, bytes.len()))
+                    .unwrap_or(false)
+                {
+                    axum::response::IntoResponse::into_response((
+                        axum::http::StatusCode::RANGE_NOT_SATISFIABLE,
+                        [
+                            (axum::http::header::CONTENT_RANGE, format!("bytes */{}", bytes.len())),
+                            (axum::http::header::ACCEPT_RANGES, "bytes".to_string()),
+                            (axum::http::header::ETAG, etag),
+                        ],
+                    ))
+                } else {
+                    let range =
+                        range_header.and_then(|value| $crate::byte_range(value, bytes.len()));
+                    let len = bytes.len();
+                    let (status, bytes) = match range.clone() {
+                        Some(range) => (
+                            axum::http::StatusCode::PARTIAL_CONTENT,
+                            match bytes {
+                                std::borrow::Cow::Borrowed(bytes) => {
+                                    std::borrow::Cow::Borrowed(&bytes[range])
+                                }
+                                std::borrow::Cow::Owned(bytes) => {
+                                    std::borrow::Cow::Owned(bytes[range].to_vec())
+                                }
+                            },
+                        ),
+                        None => (axum::http::StatusCode::OK, bytes),
+                    };
+                    let mut response = axum::response::IntoResponse::into_response((
+                        status,
+                        [
+                            (axum::http::header::CONTENT_TYPE, content_type.to_string()),
+                            (axum::http::header::ETAG, etag),
+                            (axum::http::header::CACHE_CONTROL, cache_control),
+                            (axum::http::header::VARY, "accept-encoding".to_string()),
+                            (axum::http::header::ACCEPT_RANGES, "bytes".to_string()),
+                        ],
+                        bytes,
+                    ));
+                    if let Some(range) = range {
+                        response.headers_mut().insert(
+                            axum::http::header::CONTENT_RANGE,
+                            format!("bytes {}-{}/{}", range.start, range.end - 1, len)
+                                .parse()
+                                .unwrap(),
+                        );
+                    }
+                    response
+                };
+                if let Some((encoding, _)) = encoded {
+                    response.headers_mut().insert(
+                        axum::http::header::CONTENT_ENCODING,
+                        axum::http::HeaderValue::from_static(encoding),
+                    );
+                }
+                response
+            }
+            _ => $crate::embed_static_files!(@not_found $assets, $prefix $(, $not_found)?),
+        }
+    }};
+
     ($folder:expr) => {
-        embed_static_files!($folder, "/", get_files);
+        $crate::embed_static_files!($folder, "/", get_files);
     };
     ($folder:expr, $prefix:expr) => {
-        embed_static_files!($folder, $prefix, get_files);
+        $crate::embed_static_files!($folder, $prefix, get_files);
     };
 
     ($folder:expr, $prefix:expr, $ident:ident) => {
+        $crate::embed_static_files!($folder, $prefix, $ident, Assets);
+    };
+
+    (
+        $folder:expr,
+        $prefix:expr,
+        $ident:ident,
+        $assets:ident
+        $(, max_age = $max_age:literal)?
+        $(, not_found = $not_found:path)?
+    ) => {
         #[derive(ryde::StaticFiles)]
-        #[folder($folder)]
+        #[folder($folder $(, max_age = $max_age)?)]
         #[prefix($prefix)]
-        pub struct Assets;
-
-        pub async fn $ident(uri: axum::http::Uri) -> impl axum::response::IntoResponse {
-            match Assets::get(uri.path()) {
-                Some((content_type, bytes)) => (
-                    axum::http::StatusCode::OK,
-                    [(axum::http::header::CONTENT_TYPE, content_type)],
-                    bytes,
-                ),
-                None => (
-                    axum::http::StatusCode::NOT_FOUND,
-                    [(axum::http::header::CONTENT_TYPE, "text/html; charset=utf-8")],
-                    "not found".as_bytes(),
-                ),
-            }
+        pub struct $assets;
+
+        pub async fn $ident(
+            uri: axum::http::Uri,
+            headers: axum::http::HeaderMap,
+        ) -> axum::response::Response {
+            $crate::embed_static_files!(@serve $assets, uri.path(), uri.query(), headers, $prefix $(, $not_found)?)
         }
     };
 }
 
+#[macro_export]
+macro_rules! embed_static_file {
+    ($file:expr, $ident:ident, $asset:ident $(, max_age = $max_age:literal)?) => {
+        #[derive(ryde::StaticFiles)]
+        #[file($file $(, max_age = $max_age)?)]
+        pub struct $asset;
+
+        pub async fn $ident(headers: axum::http::HeaderMap) -> axum::response::Response {
+            $crate::embed_static_files!(@serve $asset, $asset::FILES[0], None, headers, "/")
+        }
+    };
+}
+
+pub fn fingerprint_path(path: &str, hash: u64) -> String {
+    let (dir, file) = path.split_at(path.rfind('/').map(|i| i + 1).unwrap_or(0));
+    match file.rsplit_once('.') {
+        Some((name, ext)) if !name.is_empty() => format!("{}{}.{:016x}.{}", dir, name, hash, ext),
+        _ => format!("{}.{:016x}", path, hash),
+    }
+}
+
+pub fn split_fingerprint(path: &str) -> Option<(String, u64)> {
+    let (dir, file) = path.split_at(path.rfind('/').map(|i| i + 1).unwrap_or(0));
+    let is_hash = |hash: &str| hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    let (rest, last) = file.rsplit_once('.')?;
+    let (name, hash) = match rest.rsplit_once('.') {
+        Some((name, hash)) if is_hash(hash) => (format!("{}{}.{}", dir, name, last), hash),
+        _ if is_hash(last) && !rest.is_empty() => (format!("{}{}", dir, rest), last),
+        _ => return None,
+    };
+
+    Some((name, u64::from_str_radix(hash, 16).ok()?))
+}
+
+pub fn byte_range(range: &str, len: usize) -> Option<std::ops::Range<usize>> {
+    let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix = suffix.parse::<usize>().ok()?;
+            (len - suffix.min(len), len)
+        }
+        (start, "") => (start.parse::<usize>().ok()?, len),
+        (start, end) => (
+            start.parse::<usize>().ok()?,
+            end.parse::<usize>().ok()?.saturating_add(1).min(len),
+        ),
+    };
+
+    match start < end {
+        true => Some(start..end),
+        false => None,
+    }
+}
+
+pub fn byte_range_unsatisfiable(range: &str, len: usize) -> bool {
+    let Some((start, end)) = range
+        .trim()
+        .strip_prefix("bytes=")
+        .and_then(|range| range.split_once('-'))
+    else {
+        return false;
+    };
+    match (start.trim(), end.trim()) {
+        ("", suffix) => suffix
+            .parse::<usize>()
+            .map(|suffix| suffix == 0 || len == 0)
+            .unwrap_or(false),
+        (start, end) => match (start.parse::<usize>(), end) {
+            (Ok(start), "") => start >= len,
+            (Ok(start), end) => end
+                .parse::<usize>()
+                .map(|end| start <= end && start >= len)
+                .unwrap_or(false),
+            (Err(_), _) => false,
+        },
+    }
+}
+
 pub fn dotenv(s: &str) -> Option<String> {
     use std::collections::HashMap;
     use std::env;
@@ -253,7 +676,111 @@ pub fn dotenv(s: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::dotenv;
+    use super::{dotenv, download, html, Component, Render};
+    use axum::{
+        body::Body,
+        extract::{Path, Request},
+        http::{
+            header::{
+                ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_ENCODING,
+                CONTENT_TYPE, ETAG, IF_NONE_MATCH,
+            },
+            StatusCode,
+        },
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn flash_works() {
+        use super::{redirect_with_flash, Flash, IntoResponse, SET_COOKIE};
+
+        let response = redirect_with_flash("/".into(), "Saved post");
+        assert_eq!(StatusCode::SEE_OTHER, response.status());
+        assert_eq!(
+            "flash=Saved%20post; Path=/; HttpOnly; SameSite=Lax",
+            response.headers()[SET_COOKIE]
+        );
+
+        async fn index(flash: Flash) -> impl IntoResponse {
+            let message = flash.0.clone().unwrap_or_default();
+            (flash, message)
+        }
+
+        let app = Router::new().route("/", get(index));
+        let request = Request::builder()
+            .uri("/")
+            .header("cookie", "theme=dark; flash=Saved%20post")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!("flash=; Path=/; Max-Age=0", response.headers()[SET_COOKIE]);
+        assert_eq!(
+            "Saved post",
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+
+        let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert!(response.headers().get(SET_COOKIE).is_none());
+    }
+
+    #[test]
+    fn redirect_with_works() {
+        let response = super::redirect("/".into());
+        assert_eq!(StatusCode::SEE_OTHER, response.status());
+        assert_eq!("/", response.headers()[super::LOCATION]);
+
+        let response = super::redirect_with(StatusCode::MOVED_PERMANENTLY, "/new".into());
+        assert_eq!(StatusCode::MOVED_PERMANENTLY, response.status());
+        assert_eq!("/new", response.headers()[super::LOCATION]);
+    }
+
+    #[test]
+    fn config_works() {
+        #[derive(super::Config)]
+        struct AppConfig {
+            ryde_config_port: u16,
+            #[config(default = "localhost")]
+            ryde_config_host: String,
+            ryde_config_secret: Option<String>,
+            #[config(env = "HELLO")]
+            hello: String,
+        }
+
+        #[derive(super::Config)]
+        #[allow(dead_code)]
+        struct BrokenConfig {
+            ryde_config_missing: String,
+            #[config(env = "RYDE_CONFIG_BAD_PORT")]
+            port: u16,
+        }
+
+        #[derive(super::Config)]
+        struct DotenvConfig {
+            hello: String,
+        }
+
+        let lookup = |key: &str| match key {
+            "RYDE_CONFIG_PORT" => Some("9000".to_string()),
+            "RYDE_CONFIG_BAD_PORT" => Some("nine".to_string()),
+            "HELLO" => Some("WORLD".to_string()),
+            _ => None,
+        };
+
+        let config = AppConfig::from_lookup(lookup).unwrap();
+        assert_eq!(9000, config.ryde_config_port);
+        assert_eq!("localhost", config.ryde_config_host);
+        assert_eq!(None, config.ryde_config_secret);
+        assert_eq!("WORLD", config.hello);
+        assert_eq!(
+            Err("RYDE_CONFIG_MISSING is missing, RYDE_CONFIG_BAD_PORT is invalid: invalid digit found in string".into()),
+            BrokenConfig::from_lookup(lookup).map(|_| ())
+        );
+        assert_eq!("WORLD", DotenvConfig::from_env().unwrap().hello);
+    }
 
     #[test]
     fn env_works() {
@@ -261,4 +788,413 @@ mod tests {
         assert_eq!(dotenv("GOODBYE"), None);
         assert_eq!(dotenv("ABC"), Some("XYZ".into()));
     }
+
+    #[tokio::test]
+    async fn download_works() {
+        #[download("report-{id}.csv")]
+        async fn report(Path(id): Path<i64>) -> String {
+            format!("id\n{}", id)
+        }
+
+        let app = Router::new().route("/reports/:id", get(report));
+        let request = Request::builder()
+            .uri("/reports/1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(
+            response.headers().get(CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"report-1.csv\""
+        );
+    }
+
+    #[tokio::test]
+    async fn download_struct_works() {
+        use super::{Download, IntoResponse};
+
+        let response = Download::from_bytes("report \"1\".csv", "id\n1")
+            .content_type("text/csv")
+            .into_response();
+        assert_eq!("text/csv", response.headers()[CONTENT_TYPE]);
+        assert_eq!(
+            "attachment; filename=\"report \\\"1\\\".csv\"",
+            response.headers()[CONTENT_DISPOSITION]
+        );
+        assert_eq!(
+            "id\n1",
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+
+        let response = Download::from_file("examples/static_files/static/test.css")
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(
+            "attachment; filename=\"test.css\"",
+            response.headers()[CONTENT_DISPOSITION]
+        );
+        assert_eq!(
+            std::fs::read("examples/static_files/static/test.css").unwrap(),
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+
+        let response = Download::from_reader("report.csv", &b"id\n1\n2"[..])
+            .content_type("text/csv")
+            .into_response();
+        assert_eq!("text/csv", response.headers()[CONTENT_TYPE]);
+        assert_eq!(
+            None,
+            response.headers().get(axum::http::header::CONTENT_LENGTH)
+        );
+        assert_eq!(
+            "id\n1\n2",
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn static_file_downloads_work() {
+        use super::IntoResponse;
+
+        embed_static_files!("examples/static_files/static", "/assets/", get_files, Files);
+
+        let _ = get_files;
+        let response = Files::download("/assets/test.css").unwrap().into_response();
+        assert_eq!("text/css", response.headers()[CONTENT_TYPE]);
+        assert_eq!(
+            "attachment; filename=\"test.css\"",
+            response.headers()[CONTENT_DISPOSITION]
+        );
+        assert_eq!(
+            Files::get("/assets/test.css").unwrap().1,
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+        assert!(Files::download("/assets/missing.css").is_none());
+    }
+
+    #[test]
+    fn multiple_static_folders_work() {
+        embed_static_files!("examples/static_files/static", "/", get_files, Files);
+        embed_static_files!("examples/html/static", "/vendor/", get_vendor, Vendor);
+
+        let _ = (get_files, get_vendor);
+        assert_eq!(Some("text/css"), Files::get("/test.css").map(|(ct, _)| ct));
+        assert_eq!(
+            Some("text/css"),
+            Vendor::get("/vendor/tailwind.css").map(|(ct, _)| ct)
+        );
+        assert_eq!(None, Files::get("/vendor/tailwind.css"));
+        assert!(render_static_files!(Vendor)
+            .to_string()
+            .starts_with(r#"<link rel="stylesheet" href="/vendor/tailwind."#));
+    }
+
+    #[tokio::test]
+    async fn static_files_are_cached() {
+        embed_static_files!(
+            "examples/static_files/static",
+            "/",
+            get_files,
+            Files,
+            max_age = 86400
+        );
+
+        let app = Router::new().fallback(get_files);
+        let request = Request::builder()
+            .uri("/test.css")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let etag = response.headers().get(ETAG).unwrap().clone();
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            response.headers().get(CACHE_CONTROL).unwrap(),
+            "public, max-age=86400"
+        );
+
+        let request = Request::builder()
+            .uri("/test.css")
+            .header(IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+    }
+
+    #[test]
+    fn fingerprint_paths_work() {
+        assert_eq!(
+            "/assets/app.00000000003fa9c2.css",
+            super::fingerprint_path("/assets/app.css", 0x3fa9c2)
+        );
+        assert_eq!(
+            "/app.css.00000000003fa9c2.gz",
+            super::fingerprint_path("/app.css.gz", 0x3fa9c2)
+        );
+        assert_eq!(
+            "/LICENSE.00000000003fa9c2",
+            super::fingerprint_path("/LICENSE", 0x3fa9c2)
+        );
+        assert_eq!(
+            Some(("/assets/app.css".to_string(), 0x3fa9c2)),
+            super::split_fingerprint("/assets/app.00000000003fa9c2.css")
+        );
+        assert_eq!(
+            Some(("/app.css.gz".to_string(), 0x3fa9c2)),
+            super::split_fingerprint("/app.css.00000000003fa9c2.gz")
+        );
+        assert_eq!(
+            Some(("/LICENSE".to_string(), 0x3fa9c2)),
+            super::split_fingerprint("/LICENSE.00000000003fa9c2")
+        );
+        assert_eq!(None, super::split_fingerprint("/assets/app.css"));
+        assert_eq!(None, super::split_fingerprint("/assets/app.3fa9c2.css"));
+    }
+
+    #[test]
+    fn byte_range_works() {
+        assert_eq!(Some(0..5), super::byte_range("bytes=0-4", 10));
+        assert_eq!(Some(5..10), super::byte_range("bytes=5-", 10));
+        assert_eq!(Some(7..10), super::byte_range("bytes=-3", 10));
+        assert_eq!(Some(8..10), super::byte_range("bytes=8-20", 10));
+        assert_eq!(None, super::byte_range("bytes=10-", 10));
+        assert_eq!(None, super::byte_range("bytes=0-1,4-5", 10));
+        assert_eq!(None, super::byte_range("items=0-4", 10));
+        assert!(super::byte_range_unsatisfiable("bytes=10-", 10));
+        assert!(super::byte_range_unsatisfiable("bytes=12-20", 10));
+        assert!(super::byte_range_unsatisfiable("bytes=-0", 10));
+        assert!(super::byte_range_unsatisfiable("bytes=-3", 0));
+        assert!(!super::byte_range_unsatisfiable("bytes=8-20", 10));
+        assert!(!super::byte_range_unsatisfiable("bytes=5-3", 10));
+        assert!(!super::byte_range_unsatisfiable("bytes=0-1,14-15", 10));
+        assert!(!super::byte_range_unsatisfiable("items=10-", 10));
+    }
+
+    #[tokio::test]
+    async fn static_file_ranges_work() {
+        embed_static_files!("examples/static_files/static", "/", get_files, Files);
+
+        let app = Router::new().fallback(get_files);
+        let request = Request::builder()
+            .uri("/test.css")
+            .header(axum::http::header::RANGE, "bytes=0-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let (_, bytes) = Files::get("/test.css").unwrap();
+
+        assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+        assert_eq!(
+            format!("bytes 0-1/{}", bytes.len()),
+            response.headers()[axum::http::header::CONTENT_RANGE]
+        );
+        assert_eq!(
+            &bytes[..2],
+            &response.into_body().collect().await.unwrap().to_bytes()[..]
+        );
+
+        let request = Request::builder()
+            .uri("/test.css")
+            .header(axum::http::header::RANGE, format!("bytes={}-", bytes.len()))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(StatusCode::RANGE_NOT_SATISFIABLE, response.status());
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_RANGE],
+            format!("bytes */{}", bytes.len())
+        );
+    }
+
+    #[test]
+    fn static_file_mime_types_work() {
+        #[derive(super::StaticFiles)]
+        #[folder("examples/static_files/static")]
+        #[prefix("/")]
+        #[mime(gz = "application/gzip")]
+        struct Files;
+
+        assert_eq!("text/css", Files::get("/test.css").unwrap().0);
+        assert_eq!("application/gzip", Files::get("/test.css.gz").unwrap().0);
+        assert_eq!("application/gzip", Files::content_type("/new.css.gz"));
+        assert_eq!("text/javascript", Files::content_type("/new.js"));
+        assert_eq!("application/octet-stream", Files::content_type("/new"));
+        assert_eq!("text/css", Files::find("/test.css").unwrap().0);
+    }
+
+    #[cfg(feature = "debug-fs")]
+    #[test]
+    fn debug_fs_reads_new_files() {
+        #[derive(super::StaticFiles)]
+        #[folder("examples/static_files/static")]
+        #[prefix("/")]
+        struct Files;
+
+        let path = "examples/static_files/static/debug-fs-new.txt";
+        std::fs::write(path, "new").unwrap();
+        let found = Files::find("/debug-fs-new.txt");
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            Some(("text/plain; charset=utf-8", b"new".to_vec())),
+            found.map(|(content_type, bytes)| (content_type, bytes.into_owned()))
+        );
+        assert_eq!(None, Files::load("/../Cargo.toml"));
+        assert_eq!(None, Files::load("/./test.css"));
+    }
+
+    #[test]
+    fn static_file_urls_work() {
+        embed_static_files!("examples/static_files/static", "/assets/", get_files, Files);
+
+        assert_eq!(
+            Some(format!("/assets/test.{:016x}.css", Files::TEST_CSS_HASH)),
+            Files::url("test.css")
+        );
+        assert_eq!(None, Files::url("missing.css"));
+    }
+
+    #[tokio::test]
+    async fn fingerprinted_static_files_are_immutable() {
+        embed_static_files!("examples/static_files/static", "/", get_files, Files);
+
+        let app = Router::new().fallback(get_files);
+        let request = Request::builder()
+            .uri(Files::url("test.css").unwrap())
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(
+            response.headers()[axum::http::header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+
+        let request = Request::builder()
+            .uri(format!("/test.css?v={}", Files::TEST_CSS_HASH))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(
+            response.headers()[axum::http::header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+
+        let request = Request::builder()
+            .uri("/test.css?v=1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(
+            response.headers()[axum::http::header::CACHE_CONTROL],
+            format!("public, max-age={}", Files::MAX_AGE)
+        );
+
+        let request = Request::builder()
+            .uri("/test.0000000000000001.css")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+
+        assert_eq!(
+            response.headers()[axum::http::header::CACHE_CONTROL],
+            format!("public, max-age={}", Files::MAX_AGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn precompressed_static_files_work() {
+        embed_static_files!("examples/static_files/static", "/", get_files, Files);
+
+        let app = Router::new().fallback(get_files);
+        let request = Request::builder()
+            .uri("/test.css")
+            .header(ACCEPT_ENCODING, "br;q=0, gzip")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(
+            Files::get_encoded("/test.css", "gzip"),
+            Files::get("/test.css.gz").map(|(_, bytes)| bytes)
+        );
+
+        let request = Request::builder()
+            .uri("/test.css")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(None, response.headers().get(CONTENT_ENCODING));
+    }
+
+    #[tokio::test]
+    async fn static_files_not_found_works() {
+        embed_static_files!("examples/static_files/static", "/", get_files, Files);
+        embed_static_files!(
+            "examples/static_files/static",
+            "/",
+            get_themed_files,
+            ThemedFiles,
+            not_found = missing
+        );
+
+        async fn missing() -> (StatusCode, &'static str) {
+            (StatusCode::NOT_FOUND, "missing")
+        }
+
+        let request = Request::builder()
+            .uri("/nope.css")
+            .body(Body::empty())
+            .unwrap();
+        let response = Router::new()
+            .fallback(get_files)
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "text/plain; charset=utf-8"
+        );
+
+        let request = Request::builder()
+            .uri("/nope.css")
+            .body(Body::empty())
+            .unwrap();
+        let response = Router::new()
+            .fallback(get_themed_files)
+            .oneshot(request)
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(&body[..], b"missing");
+    }
+
+    #[tokio::test]
+    async fn single_static_file_works() {
+        embed_static_file!("examples/static_files/static/test.css", get_style, Style);
+
+        let app = Router::new().route("/style.css", get(get_style));
+        let request = Request::builder()
+            .uri("/style.css")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(&["/test.css"], Style::FILES);
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), "text/css");
+    }
 }
@@ -1,17 +1,944 @@
 extern crate self as router;
 
 pub use ryde_macros::{router, routes, url};
+use std::borrow::Cow;
+
+pub fn percent_encode<'a, S: Into<Cow<'a, str>>>(input: S) -> Cow<'a, str> {
+    let input = input.into();
+    fn needs_encoding(b: u8) -> bool {
+        !(b.is_ascii_alphanumeric() || b == b'-' || b == b'.' || b == b'_' || b == b'~')
+    }
+
+    if let Some(first) = input.bytes().position(needs_encoding) {
+        let mut output = String::from(&input[0..first]);
+        output.reserve(input.len() - first);
+        for b in input[first..].bytes() {
+            match needs_encoding(b) {
+                true => output.push_str(&format!("%{:02X}", b)),
+                false => output.push(b as char),
+            }
+        }
+        Cow::Owned(output)
+    } else {
+        input
+    }
+}
+
+pub fn percent_encode_path(input: &str) -> String {
+    input
+        .split('/')
+        .map(percent_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub trait ToQueryValue {
+    fn to_query_value(&self) -> Option<String>;
+
+    fn to_query_values(&self) -> Vec<String> {
+        self.to_query_value().into_iter().collect()
+    }
+}
+
+macro_rules! impl_to_query_value {
+    ($t:ty) => {
+        impl ToQueryValue for $t {
+            fn to_query_value(&self) -> Option<String> {
+                Some(self.to_string())
+            }
+        }
+    };
+}
+
+impl_to_query_value!(u8);
+impl_to_query_value!(i8);
+impl_to_query_value!(u16);
+impl_to_query_value!(i16);
+impl_to_query_value!(u32);
+impl_to_query_value!(i32);
+impl_to_query_value!(u64);
+impl_to_query_value!(i64);
+impl_to_query_value!(usize);
+impl_to_query_value!(isize);
+impl_to_query_value!(f32);
+impl_to_query_value!(f64);
+impl_to_query_value!(bool);
+impl_to_query_value!(char);
+impl_to_query_value!(str);
+impl_to_query_value!(String);
+
+impl<T> ToQueryValue for Option<T>
+where
+    T: ToQueryValue,
+{
+    fn to_query_value(&self) -> Option<String> {
+        self.as_ref().and_then(|t| t.to_query_value())
+    }
+
+    fn to_query_values(&self) -> Vec<String> {
+        self.as_ref()
+            .map(|t| t.to_query_values())
+            .unwrap_or_default()
+    }
+}
+
+impl<T> ToQueryValue for &T
+where
+    T: ToQueryValue + ?Sized,
+{
+    fn to_query_value(&self) -> Option<String> {
+        (**self).to_query_value()
+    }
+
+    fn to_query_values(&self) -> Vec<String> {
+        (**self).to_query_values()
+    }
+}
+
+impl<T> ToQueryValue for [T]
+where
+    T: ToQueryValue,
+{
+    fn to_query_value(&self) -> Option<String> {
+        let values = self.to_query_values();
+        match values.is_empty() {
+            true => None,
+            false => Some(values.join(",")),
+        }
+    }
+
+    fn to_query_values(&self) -> Vec<String> {
+        self.iter().flat_map(|t| t.to_query_values()).collect()
+    }
+}
+
+impl<T> ToQueryValue for Vec<T>
+where
+    T: ToQueryValue,
+{
+    fn to_query_value(&self) -> Option<String> {
+        self.as_slice().to_query_value()
+    }
+
+    fn to_query_values(&self) -> Vec<String> {
+        self.as_slice().to_query_values()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comma<T>(pub T);
+
+impl<T> ToQueryValue for Comma<T>
+where
+    T: ToQueryValue,
+{
+    fn to_query_value(&self) -> Option<String> {
+        self.0.to_query_value()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct Pagination {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            per_page: Self::PER_PAGE,
+        }
+    }
+}
+
+impl Pagination {
+    pub const PER_PAGE: u32 = 20;
+    pub const MAX_PER_PAGE: u32 = 100;
+
+    pub fn page(&self) -> u32 {
+        self.page.max(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page.clamp(1, Self::MAX_PER_PAGE)
+    }
+
+    pub fn offset(&self) -> u32 {
+        (self.page() - 1).saturating_mul(self.per_page())
+    }
+
+    pub fn next(&self) -> Self {
+        Self {
+            page: self.page().saturating_add(1),
+            per_page: self.per_page(),
+        }
+    }
+
+    pub fn prev(&self) -> Option<Self> {
+        match self.page() {
+            1 => None,
+            page => Some(Self {
+                page: page - 1,
+                per_page: self.per_page(),
+            }),
+        }
+    }
+
+    pub fn query(&self) -> Vec<(&'static str, Vec<String>)> {
+        vec![
+            ("page", vec![self.page().to_string()]),
+            ("per_page", vec![self.per_page().to_string()]),
+        ]
+    }
+
+    pub fn next_url(&self, route: impl Fn(&[(&str, Vec<String>)]) -> String) -> String {
+        route(&self.next().query())
+    }
+
+    pub fn prev_url(&self, route: impl Fn(&[(&str, Vec<String>)]) -> String) -> Option<String> {
+        self.prev().map(|prev| route(&prev.query()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct Cursor {
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub limit: u32,
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self {
+            after: None,
+            before: None,
+            limit: Pagination::PER_PAGE,
+        }
+    }
+}
+
+impl Cursor {
+    pub fn limit(&self) -> u32 {
+        self.limit.clamp(1, Pagination::MAX_PER_PAGE)
+    }
+
+    pub fn next(&self, last: impl std::fmt::Display) -> Self {
+        Self {
+            after: Some(last.to_string()),
+            before: None,
+            limit: self.limit(),
+        }
+    }
+
+    pub fn prev(&self, first: impl std::fmt::Display) -> Self {
+        Self {
+            after: None,
+            before: Some(first.to_string()),
+            limit: self.limit(),
+        }
+    }
+
+    pub fn query(&self) -> Vec<(&'static str, Vec<String>)> {
+        vec![
+            ("after", self.after.to_query_values()),
+            ("before", self.before.to_query_values()),
+            ("limit", vec![self.limit().to_string()]),
+        ]
+    }
+
+    pub fn next_url(
+        &self,
+        last: impl std::fmt::Display,
+        route: impl Fn(&[(&str, Vec<String>)]) -> String,
+    ) -> String {
+        route(&self.next(last).query())
+    }
+
+    pub fn prev_url(
+        &self,
+        first: impl std::fmt::Display,
+        route: impl Fn(&[(&str, Vec<String>)]) -> String,
+    ) -> String {
+        route(&self.prev(first).query())
+    }
+}
+
+pub fn query_string(params: &[(&str, Vec<String>)]) -> String {
+    let query = params
+        .iter()
+        .flat_map(|(key, values)| {
+            values.iter().map(move |value| {
+                format!(
+                    "{}={}",
+                    percent_encode(*key),
+                    percent_encode(value.as_str())
+                )
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    match query.is_empty() {
+        true => query,
+        false => format!("?{}", query),
+    }
+}
+
+pub const fn has_path_params(params: &[&str], given: &[&str]) -> bool {
+    let mut i = 0;
+    while i < params.len() {
+        let param = params[i].as_bytes();
+        let mut found = false;
+        let mut j = 0;
+        while j < given.len() && !found {
+            let name = given[j].as_bytes();
+            found = name.len() == param.len();
+            let mut k = 0;
+            while found && k < name.len() {
+                found = name[k] == param[k];
+                k += 1;
+            }
+            j += 1;
+        }
+        if !found {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+pub fn url_with_base(base: &str, url: impl AsRef<str>) -> String {
+    let url = url.as_ref();
+
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        url.trim_start_matches('/')
+    )
+}
+
+fn segment_param(segment: &str) -> Option<(&str, bool)> {
+    if segment.starts_with("{{") {
+        return None;
+    }
+    if let Some(name) = segment
+        .strip_prefix('*')
+        .or_else(|| segment.strip_prefix("{*")?.strip_suffix('}'))
+    {
+        return Some((name, true));
+    }
+
+    segment
+        .strip_prefix(':')
+        .or_else(|| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| (name, false))
+}
+
+pub fn sitemap(base: &str, routes: &[Route]) -> String {
+    let urls = routes
+        .iter()
+        .filter(|route| route.method == "GET")
+        .filter(|route| {
+            !route
+                .path
+                .split('/')
+                .any(|segment| segment_param(segment).is_some())
+        })
+        .map(|route| {
+            format!(
+                "<url><loc>{}</loc></url>",
+                crate::escape(url_with_base(base, route.path))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">{}</urlset>"#,
+        urls
+    )
+}
+
+pub fn robots(base: &str) -> String {
+    format!(
+        "User-agent: *\nAllow: /\nSitemap: {}\n",
+        url_with_base(base, "/sitemap.xml")
+    )
+}
+
+pub fn route_list(routes: &[Route]) -> String {
+    let mut routes = routes.to_vec();
+    routes.sort_by(|a, b| (a.path, a.method, a.handler).cmp(&(b.path, b.method, b.handler)));
+
+    routes
+        .iter()
+        .map(|route| format!("{}\n", route))
+        .collect::<String>()
+}
+
+pub fn route_table(routes: &[Route]) -> axum::response::Html<String> {
+    let rows = routes
+        .iter()
+        .map(|route| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                crate::escape(route.method),
+                crate::escape(route.path),
+                crate::escape(route.handler)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("");
+
+    axum::response::Html(format!(
+        "<table><thead><tr><th>method</th><th>path</th><th>handler</th></tr></thead><tbody>{}</tbody></table>",
+        rows
+    ))
+}
+
+pub fn typescript(routes: &[Route]) -> String {
+    let mut seen = std::collections::HashSet::new();
+    let mut output = String::from(
+        "type QueryValue = string | number | boolean | (string | number | boolean)[];\n\n\
+         function query(params: Record<string, QueryValue | undefined> = {}): string {\n  \
+         const search = new URLSearchParams();\n  \
+         for (const [key, value] of Object.entries(params)) {\n    \
+         if (value === undefined) continue;\n    \
+         for (const item of Array.isArray(value) ? value : [value]) search.append(key, String(item));\n  \
+         }\n  \
+         const s = search.toString();\n  \
+         return s ? `?${s}` : \"\";\n\
+         }\n",
+    );
+    for route in routes
+        .iter()
+        .filter(|route| !route.handler.is_empty() && seen.insert(route.handler))
+    {
+        let mut params = vec![];
+        let path = route
+            .path
+            .split('/')
+            .map(|segment| match segment_param(segment) {
+                Some((name, true)) => {
+                    params.push(name);
+                    format!(
+                        "${{String({}).split(\"/\").map(encodeURIComponent).join(\"/\")}}",
+                        name
+                    )
+                }
+                Some((name, false)) => {
+                    params.push(name);
+                    format!("${{encodeURIComponent(String({}))}}", name)
+                }
+                None => segment.replace('`', "\\`").replace("${", "\\${"),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let args = params
+            .iter()
+            .map(|name| format!("{}: string | number", name))
+            .chain(std::iter::once(
+                "params?: Record<string, QueryValue | undefined>".to_string(),
+            ))
+            .collect::<Vec<_>>()
+            .join(", ");
+        output.push_str(&format!(
+            "\nexport function {}({}): string {{\n  return `{}${{query(params)}}`;\n}}\n",
+            route.handler.replace("::", "_"),
+            args,
+            path
+        ));
+    }
+
+    output
+}
+
+pub fn openapi(title: &str, version: &str, routes: &[Route]) -> String {
+    fn json(s: &str) -> String {
+        let mut output = String::from("\"");
+        for c in s.chars() {
+            match c {
+                '"' => output.push_str("\\\""),
+                '\\' => output.push_str("\\\\"),
+                c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+                c => output.push(c),
+            }
+        }
+        output.push('"');
+        output
+    }
+
+    let mut paths: Vec<(String, Vec<String>)> = vec![];
+    for route in routes.iter().filter(|route| route.method != "ANY") {
+        let mut params = vec![];
+        let path = route
+            .path
+            .split('/')
+            .map(|segment| match segment_param(segment) {
+                Some((name, _)) => {
+                    params.push(format!(
+                        r#"{{"name":{},"in":"path","required":true,"schema":{{"type":"string"}}}}"#,
+                        json(name)
+                    ));
+                    format!("{{{}}}", name)
+                }
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let operation = format!(
+            r#"{}:{{"summary":{},"parameters":[{}],"responses":{{"200":{{"description":"OK"}}}}}}"#,
+            json(&route.method.to_lowercase()),
+            json(route.handler),
+            params.join(",")
+        );
+        match paths.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, operations)) => operations.push(operation),
+            None => paths.push((path, vec![operation])),
+        }
+    }
+
+    let paths = paths
+        .iter()
+        .map(|(path, operations)| format!("{}:{{{}}}", json(path), operations.join(",")))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!(
+        r#"{{"openapi":"3.1.0","info":{{"title":{},"version":{}}},"paths":{{{}}}}}"#,
+        json(title),
+        json(version),
+        paths
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub handler: &'static str,
+}
+
+impl std::fmt::Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.method, self.path, self.handler)
+    }
+}
+
+impl Route {
+    pub fn http_method(&self) -> Option<axum::http::Method> {
+        match self.method {
+            "ANY" => None,
+            method => Some(
+                axum::http::Method::from_bytes(method.as_bytes())
+                    .expect("routes use valid methods"),
+            ),
+        }
+    }
+
+    pub fn matches(&self, method: &str, path: &str) -> bool {
+        self.params(method, path).is_some()
+    }
+
+    pub fn params(&self, method: &str, path: &str) -> Option<Vec<(&'static str, String)>> {
+        if self.method != "ANY" && !self.method.eq_ignore_ascii_case(method) {
+            return None;
+        }
+        let path = path.split('?').next().unwrap_or_default();
+        let mut segments = path.trim_start_matches('/').split('/');
+        let mut params = vec![];
+
+        for template in self.path.trim_start_matches('/').split('/') {
+            let param = segment_param(template);
+            if let Some((name, true)) = param {
+                let rest = segments.by_ref().collect::<Vec<_>>().join("/");
+                if rest.is_empty() {
+                    return None;
+                }
+                params.push((name, percent_decode(&rest)));
+                return Some(params);
+            }
+            match (segments.next(), param.map(|(name, _)| name)) {
+                (Some(segment), Some(name)) if !segment.is_empty() => {
+                    params.push((name, percent_decode(segment)))
+                }
+                (Some(segment), None) if segment == template => {}
+                _ => return None,
+            }
+        }
+
+        match segments.next() {
+            Some(_) => None,
+            None => Some(params),
+        }
+    }
+}
+
+pub fn parse_route(
+    routes: &[Route],
+    method: &str,
+    path: &str,
+) -> Option<(Route, Vec<(&'static str, String)>)> {
+    routes
+        .iter()
+        .filter_map(|route| Some((*route, route.params(method, path)?)))
+        .max_by_key(|(route, _)| {
+            route
+                .path
+                .trim_start_matches('/')
+                .split('/')
+                .map(|segment| match segment_param(segment) {
+                    None => 2,
+                    Some((_, false)) => 1,
+                    Some((_, true)) => 0,
+                })
+                .collect::<Vec<u8>>()
+        })
+}
+
+pub fn url_for(routes: &[Route], handler: &str, params: &[(&str, Vec<String>)]) -> Option<String> {
+    let route = routes.iter().find(|route| route.handler == handler)?;
+    let mut used = vec![];
+    let path = route
+        .path
+        .split('/')
+        .map(|segment| match segment_param(segment) {
+            Some((name, wildcard)) => {
+                let (_, values) = params.iter().find(|(key, _)| *key == name)?;
+                used.push(name);
+                let value = values.first()?;
+                Some(match wildcard {
+                    true => percent_encode_path(value),
+                    false => percent_encode(value.as_str()).into_owned(),
+                })
+            }
+            None => Some(segment.to_string()),
+        })
+        .collect::<Option<Vec<_>>>()?
+        .join("/");
+    let query = params
+        .iter()
+        .filter(|(key, _)| !used.contains(key))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    Some(format!("{}{}", path, query_string(&query)))
+}
+
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                output.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                output.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchedRoute {
+    pub route: Route,
+    pub params: Vec<(&'static str, String)>,
+}
+
+#[axum::async_trait]
+impl<S> axum::extract::FromRequestParts<S> for MatchedRoute
+where
+    S: Send + Sync,
+{
+    type Rejection = axum::http::StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let routes = parts
+            .extensions
+            .get::<&'static [Route]>()
+            .ok_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+        let path = match parts.extensions.get::<axum::extract::OriginalUri>() {
+            Some(uri) => uri.path(),
+            None => parts.uri.path(),
+        };
+
+        parse_route(routes, parts.method.as_str(), path)
+            .map(|(route, params)| MatchedRoute { route, params })
+            .ok_or(axum::http::StatusCode::NOT_FOUND)
+    }
+}
+
+pub fn on_method<H, T, S>(method: &str, handler: H) -> axum::routing::MethodRouter<S>
+where
+    H: axum::handler::Handler<T, S>,
+    T: 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    let method =
+        axum::http::Method::from_bytes(method.as_bytes()).expect("on_method needs a valid method");
+
+    axum::routing::any(handler).layer(axum::middleware::from_fn(
+        move |request: axum::extract::Request, next: axum::middleware::Next| {
+            let allowed = request.method() == method;
+            async move {
+                match allowed {
+                    true => next.run(request).await,
+                    false => axum::response::IntoResponse::into_response(
+                        axum::http::StatusCode::METHOD_NOT_ALLOWED,
+                    ),
+                }
+            }
+        },
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormAttrs {
+    pub action: String,
+    pub method: &'static str,
+    pub method_field: crate::Component,
+}
+
+pub fn form_attrs(method: &str, action: impl Into<String>) -> FormAttrs {
+    let method = method.to_ascii_uppercase();
+    let (form_method, method_field) = match method.as_str() {
+        "GET" => ("get", String::new()),
+        "PUT" | "PATCH" | "DELETE" => (
+            "post",
+            format!(r#"<input type="hidden" name="_method" value="{}">"#, method),
+        ),
+        _ => ("post", String::new()),
+    };
+
+    FormAttrs {
+        action: action.into(),
+        method: form_method,
+        method_field: crate::Component { html: method_field },
+    }
+}
+
+const METHOD_OVERRIDE_PEEK: usize = 8 * 1024;
+
+struct PeekedBody {
+    prefix: Option<axum::body::Bytes>,
+    body: axum::body::Body,
+}
+
+impl http_body::Body for PeekedBody {
+    type Data = axum::body::Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        match self.prefix.take() {
+            Some(prefix) if !prefix.is_empty() => {
+                std::task::Poll::Ready(Some(Ok(http_body::Frame::data(prefix))))
+            }
+            _ => std::pin::Pin::new(&mut self.body).poll_frame(cx),
+        }
+    }
+}
+
+pub async fn method_override(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::http::{header::CONTENT_TYPE, Method};
+
+    fn override_method(input: &str) -> Option<Method> {
+        input
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("_method="))
+            .find_map(|value| match value.to_ascii_uppercase().as_str() {
+                "PUT" => Some(Method::PUT),
+                "PATCH" => Some(Method::PATCH),
+                "DELETE" => Some(Method::DELETE),
+                _ => None,
+            })
+    }
+
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let mut method = override_method(request.uri().query().unwrap_or_default());
+    let is_form = request.headers().get(CONTENT_TYPE).is_some_and(|value| {
+        value
+            .as_bytes()
+            .starts_with(b"application/x-www-form-urlencoded")
+    });
+    let mut request = match (&method, is_form) {
+        (None, true) => {
+            let (parts, mut body) = request.into_parts();
+            let mut prefix = vec![];
+            while prefix.len() < METHOD_OVERRIDE_PEEK {
+                match http_body_util::BodyExt::frame(&mut body).await {
+                    Some(Ok(frame)) => {
+                        if let Ok(data) = frame.into_data() {
+                            prefix.extend_from_slice(&data);
+                        }
+                    }
+                    Some(Err(_)) => {
+                        return axum::response::IntoResponse::into_response(
+                            axum::http::StatusCode::BAD_REQUEST,
+                        )
+                    }
+                    None => break,
+                }
+            }
+            method = override_method(&String::from_utf8_lossy(&prefix));
+            let body = PeekedBody {
+                prefix: Some(prefix.into()),
+                body,
+            };
+            axum::extract::Request::from_parts(parts, axum::body::Body::new(body))
+        }
+        _ => request,
+    };
+    if let Some(method) = method {
+        *request.method_mut() = method;
+    }
+
+    next.run(request).await
+}
+
+pub fn with_method_override(router: axum::Router) -> axum::Router {
+    let service = tower::Layer::layer(&axum::middleware::from_fn(method_override), router);
+
+    axum::Router::new().fallback_service(service)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[axum::async_trait]
+impl<S> axum::extract::FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = axum::http::StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .ok_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+fn new_request_id() -> String {
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let state = std::collections::hash_map::RandomState::new();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut bits = [0u64; 2];
+    for (i, bits) in bits.iter_mut().enumerate() {
+        let mut hasher = state.build_hasher();
+        hasher.write_u64(count);
+        hasher.write_usize(i);
+        *bits = hasher.finish();
+    }
+    let high = (bits[0] & 0xffff_ffff_ffff_0fff) | 0x4000;
+    let low = (bits[1] & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+pub async fn request_id(
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(|value| value.to_string())
+        .unwrap_or_else(new_request_id);
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = axum::http::HeaderValue::from_str(&id) {
+        response.headers_mut().insert("x-request-id", value);
+    }
+
+    response
+}
+
+pub fn with_request_id(router: axum::Router) -> axum::Router {
+    router.layer(axum::middleware::from_fn(request_id))
+}
+
+pub async fn call(
+    router: &axum::Router,
+    method: &str,
+    uri: impl AsRef<str>,
+) -> axum::response::Response {
+    use tower::ServiceExt;
+
+    let request = axum::http::Request::builder()
+        .method(method)
+        .uri(uri.as_ref())
+        .body(axum::body::Body::empty())
+        .expect("call needs a valid method and uri");
+
+    match router.clone().oneshot(request).await {
+        Ok(response) => response,
+        Err(err) => match err {},
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::uri;
     use axum::{
         self,
         body::Body,
         extract::{Path, Query, Request},
         http::StatusCode,
         response::IntoResponse,
-        routing::get,
+        routing::{any, get, head, on, options, MethodFilter},
         Router,
     };
     use http_body_util::BodyExt;
@@ -92,6 +1019,1547 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn query_params_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/abc", get(abc))
+        }
+
+        async fn abc(Query(params): Query<Abc>) -> impl IntoResponse {
+            url!(abc, abc = params.abc.unwrap_or_default())
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/abc?abc=0".into()),
+            make_request(&app, "GET", "/abc").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/abc?abc=1".into()),
+            make_request(&app, "GET", "/abc?abc=1").await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shared_handlers_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(home))
+                .route("/home", get(home))
+        }
+
+        async fn home() -> String {
+            url!(home)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/".into()),
+            make_request(&app, "GET", "/home").await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn head_and_options_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/health", head(health))
+                .route("/cors", options(cors))
+        }
+
+        async fn health() -> String {
+            url!(health)
+        }
+
+        async fn cors() -> String {
+            url!(cors)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "".into()),
+            make_request(&app, "HEAD", "/health").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/cors".into()),
+            make_request(&app, "OPTIONS", "/cors").await
+        );
+        assert_eq!(
+            vec![("HEAD", "/health"), ("OPTIONS", "/cors")],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| (route.method, route.path))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn method_chains_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/items", get(items).post(create_item).put(items))
+        }
+
+        async fn items() -> String {
+            url!(items)
+        }
+
+        async fn create_item() -> String {
+            url!(create_item)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/items".into()),
+            make_request(&app, "POST", "/items").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/items".into()),
+            make_request(&app, "PUT", "/items").await
+        );
+        assert_eq!(
+            vec![("GET", "items"), ("POST", "create_item"), ("PUT", "items")],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| (route.method, route.handler))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn closure_handlers_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/healthz", get(|| async { "ok" }))
+                .route("/items", get(items).post(|| async { "created" }))
+        }
+
+        async fn items() -> String {
+            url!(items)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "ok".into()),
+            make_request(&app, "GET", "/healthz").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/items".into()),
+            make_request(&app, "GET", "/items").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "created".into()),
+            make_request(&app, "POST", "/items").await
+        );
+        assert_eq!(
+            vec![
+                ("GET", "/healthz", ""),
+                ("GET", "/items", "items"),
+                ("POST", "/items", "")
+            ],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| (route.method, route.path, route.handler))
+                .collect::<Vec<_>>()
+        );
+        assert!(!typescript(ROUTER_ROUTES).contains("export function ("));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nested_routers_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().nest("/admin", Router::new().route("/posts/:id", get(admin_post)))
+        }
+
+        async fn admin_post(Path(id): Path<u32>) -> String {
+            url!(admin_post, id)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/admin/posts/1".into()),
+            make_request(&app, "GET", "/admin/posts/1").await
+        );
+        assert_eq!("/admin/posts/:id", ROUTER_ROUTES[0].path);
+
+        Ok(())
+    }
+
+    #[test]
+    fn routes_meta_works() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(home))
+                .route("/login", get(login_form).post(login))
+                .route("/cors", options(cors))
+                .nest("/admin", Router::new().route("/posts/:id", get(admin_post)))
+        }
+
+        async fn home() {}
+
+        async fn login_form() {}
+
+        async fn login() {}
+
+        async fn cors() {}
+
+        async fn admin_post() {}
+
+        let _ = router;
+        assert_eq!(
+            vec![
+                ("GET", "/"),
+                ("GET", "/login"),
+                ("POST", "/login"),
+                ("OPTIONS", "/cors"),
+                ("GET", "/admin/posts/:id"),
+            ],
+            ROUTER_ROUTES
+                .iter()
+                .map(|Route { method, path, .. }| (*method, *path))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["home", "login_form", "login", "cors", "admin_post"],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| route.handler)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(axum::http::Method::OPTIONS),
+            ROUTER_ROUTES[3].http_method()
+        );
+    }
+
+    #[test]
+    fn routes_const_works() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(home))
+                .route("/posts/:id", get(post))
+        }
+
+        async fn home() {}
+
+        async fn post() {}
+
+        const PATHS: [&str; 2] = [ROUTER_ROUTES[0].path, ROUTER_ROUTES[1].path];
+
+        let _ = router;
+        assert_eq!(["/", "/posts/:id"], PATHS);
+    }
+
+    #[test]
+    fn route_matches_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(home))
+                .route("/xyz/:xyz", get(xyz))
+                .route("/items", get(items).put(items))
+                .nest("/admin", Router::new().route("/posts/:id", get(admin_post)))
+        }
+
+        async fn home() {}
+
+        async fn xyz() {}
+
+        async fn items() {}
+
+        async fn admin_post() {}
+
+        let matches = |method: &str, path: &str| {
+            ROUTER_ROUTES
+                .iter()
+                .any(|route| route.matches(method, path))
+        };
+
+        let _ = router;
+        assert!(matches("GET", "/"));
+        assert!(matches("GET", "/xyz/abc"));
+        assert!(matches("get", "/admin/posts/1?draft=true"));
+        assert!(matches("PUT", "/items"));
+        assert!(!matches("DELETE", "/items"));
+        assert!(!matches("GET", "/xyz"));
+        assert!(!matches("GET", "/xyz/abc/def"));
+    }
+
+    #[test]
+    fn url_for_works() {
+        assert_eq!(
+            Some("/xyz/a%20b?page=2".into()),
+            url_for(
+                ROUTER_ROUTES,
+                "xyz",
+                &[("xyz", vec!["a b".into()]), ("page", vec!["2".into()])]
+            )
+        );
+        assert_eq!(
+            Some("/docs/a/b".into()),
+            url_for(
+                &[Route {
+                    method: "GET",
+                    path: "/docs/{*path}",
+                    handler: "docs",
+                }],
+                "docs",
+                &[("path", vec!["a/b".into()])]
+            )
+        );
+        assert_eq!(None, url_for(ROUTER_ROUTES, "xyz", &[]));
+        assert_eq!(None, url_for(ROUTER_ROUTES, "missing", &[]));
+    }
+
+    #[test]
+    fn parse_route_works() {
+        let routes = [
+            Route {
+                method: "GET",
+                path: "/posts/:id",
+                handler: "post",
+            },
+            Route {
+                method: "GET",
+                path: "/posts/new",
+                handler: "new_post",
+            },
+            Route {
+                method: "GET",
+                path: "/files/*path",
+                handler: "file",
+            },
+            Route {
+                method: "GET",
+                path: "/files/:id",
+                handler: "file_by_id",
+            },
+        ];
+
+        let (route, params) = parse_route(&routes, "GET", "/posts/a%20b?page=2").unwrap();
+        assert_eq!("post", route.handler);
+        assert_eq!(vec![("id", "a b".to_string())], params);
+
+        let (route, params) = parse_route(&routes, "GET", "/posts/new").unwrap();
+        assert_eq!("new_post", route.handler);
+        assert!(params.is_empty());
+
+        let (route, params) = parse_route(&routes, "GET", "/files/a/b.txt").unwrap();
+        assert_eq!("file", route.handler);
+        assert_eq!(vec![("path", "a/b.txt".to_string())], params);
+
+        let (route, params) = parse_route(&routes, "GET", "/files/1").unwrap();
+        assert_eq!("file_by_id", route.handler);
+        assert_eq!(vec![("id", "1".to_string())], params);
+
+        assert_eq!(None, parse_route(&routes, "POST", "/posts/1"));
+        assert_eq!(None, parse_route(&routes, "GET", "/files"));
+    }
+
+    #[test]
+    fn named_path_params_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/xyz/:xyz", get(xyz))
+                .route("/filter", get(filter))
+                .nest("/admin", Router::new().route("/posts/:id", get(admin_post)))
+        }
+
+        async fn xyz() {}
+
+        async fn filter() {}
+
+        async fn admin_post() {}
+
+        let _ = router;
+        assert_eq!(
+            "/xyz/a%20b?tab=profile",
+            url!(xyz, xyz = "a b", tab = "profile")
+        );
+        assert_eq!("/admin/posts/5", url!(admin_post, id = 5));
+        assert_eq!("/filter?page=2", url!(filter, page = 2));
+    }
+
+    #[test]
+    fn query_params_keep_declaration_order() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/search", get(search))
+                .route("/xyz/:xyz", get(xyz))
+        }
+
+        async fn search() {}
+
+        async fn xyz() {}
+
+        let _ = router;
+        struct Params {
+            first: Option<u32>,
+            second: Option<&'static str>,
+            third: Option<bool>,
+        }
+
+        let params = Params {
+            first: Some(1),
+            second: None,
+            third: Some(true),
+        };
+
+        assert_eq!(
+            "/search?first=1&third=true",
+            url!(
+                search,
+                first = params.first,
+                second = params.second,
+                third = params.third
+            )
+        );
+        assert_eq!(
+            "/xyz/a?third=true&first=1",
+            url!(
+                xyz,
+                "a",
+                third = params.third,
+                second = params.second,
+                first = params.first
+            )
+        );
+    }
+
+    #[test]
+    fn custom_query_values_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/search", get(search))
+        }
+
+        async fn search() {}
+
+        enum Sort {
+            New,
+            Top,
+        }
+
+        impl ToQueryValue for Sort {
+            fn to_query_value(&self) -> Option<String> {
+                match self {
+                    Sort::New => None,
+                    Sort::Top => Some("top".into()),
+                }
+            }
+        }
+
+        let _ = router;
+        assert_eq!(
+            "/search?sort=top&all=true",
+            url!(search, sort = Sort::Top, all = true)
+        );
+        assert_eq!("/search", url!(search, sort = Sort::New));
+    }
+
+    #[test]
+    fn url_with_base_works() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(home))
+                .route("/search", get(search))
+                .route("/xyz/:xyz", get(xyz))
+        }
+
+        async fn home() {}
+
+        async fn search() {}
+
+        async fn xyz() {}
+
+        let _ = router;
+        assert_eq!(
+            "https://example.com/xyz/1",
+            url_with_base("https://example.com", url!(xyz, 1))
+        );
+        assert_eq!(
+            "https://example.com/search?q=a%20b",
+            url_with_base("https://example.com/", url!(search, q = "a b"))
+        );
+        assert_eq!(
+            "https://example.com/",
+            url_with_base("https://example.com//", url!(home))
+        );
+    }
+
+    #[tokio::test]
+    async fn pagination_works() {
+        async fn posts(Query(pagination): Query<Pagination>) -> String {
+            let next = pagination.next();
+            format!(
+                "{} {}",
+                pagination.offset(),
+                url!(xyz, "posts", page = next.page, per_page = next.per_page)
+            )
+        }
+
+        let app = Router::new().route("/posts", get(posts));
+
+        assert_eq!(
+            (StatusCode::OK, "0 /xyz/posts?page=2&per_page=20".into()),
+            make_request(&app, "GET", "/posts").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "200 /xyz/posts?page=4&per_page=100".into()),
+            make_request(&app, "GET", "/posts?page=3&per_page=500").await
+        );
+        assert_eq!(None, Pagination::default().prev());
+        assert_eq!(
+            Some(Pagination {
+                page: 1,
+                per_page: 10
+            }),
+            Pagination {
+                page: 2,
+                per_page: 10
+            }
+            .prev()
+        );
+    }
+
+    #[test]
+    fn pagination_urls_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/posts", get(posts))
+                .route("/orgs/:org/posts", get(org_posts))
+        }
+
+        async fn posts() {}
+
+        async fn org_posts() {}
+
+        let _ = (router, posts_path);
+        let pagination = Pagination {
+            page: 2,
+            per_page: 10,
+        };
+
+        assert_eq!(
+            "/posts?page=3&per_page=10",
+            pagination.next_url(posts_path_named)
+        );
+        assert_eq!(
+            Some("/posts?page=1&per_page=10".into()),
+            pagination.prev_url(posts_path_named)
+        );
+        assert_eq!(None, Pagination::default().prev_url(posts_path_named));
+        assert_eq!("/orgs/acme/posts", org_posts_path("acme"));
+        assert_eq!(
+            "/orgs/acme/posts?page=3&per_page=10",
+            pagination.next_url(|query| org_posts_path_named(
+                &[&[("org", vec!["acme".to_string()])][..], query].concat()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn cursor_works() {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/posts", get(posts))
+        }
+
+        async fn posts(Query(cursor): Query<Cursor>) -> String {
+            format!(
+                "{:?} {:?} {} {}",
+                cursor.after,
+                cursor.before,
+                cursor.limit(),
+                cursor.next_url(42, posts_path_named)
+            )
+        }
+
+        let _ = posts_path;
+        let app = router();
+
+        assert_eq!(
+            (
+                StatusCode::OK,
+                "None None 20 /posts?after=42&limit=20".into()
+            ),
+            make_request(&app, "GET", "/posts").await
+        );
+        assert_eq!(
+            (
+                StatusCode::OK,
+                "Some(\"7\") None 100 /posts?after=42&limit=100".into()
+            ),
+            make_request(&app, "GET", "/posts?after=7&limit=500").await
+        );
+        assert_eq!(
+            "/posts?before=a%20b&limit=20",
+            Cursor::default().prev_url("a b", posts_path_named)
+        );
+    }
+
+    #[test]
+    fn vec_query_params_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/search", get(search))
+                .route("/xyz/:xyz", get(xyz))
+        }
+
+        async fn search() {}
+
+        async fn xyz() {}
+
+        let _ = router;
+        let tags = vec!["a", "b c"];
+
+        assert_eq!("/search?tag=a&tag=b%20c", url!(search, tag = tags));
+        assert_eq!("/search?tag=a%2Cb%20c", url!(search, tag = Comma(&tags)));
+        assert_eq!("/search", url!(search, tag = Vec::<u32>::new()));
+        assert_eq!("/search", url!(search, tag = Comma(Vec::<u32>::new())));
+        assert_eq!("/xyz/a?tag=a&tag=b%20c", url!(xyz, "a", tag = tags));
+    }
+
+    #[test]
+    fn non_debug_values_work() {
+        struct Slug(&'static str);
+
+        impl std::fmt::Display for Slug {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+
+        impl ToQueryValue for Slug {
+            fn to_query_value(&self) -> Option<String> {
+                Some(self.0.into())
+            }
+        }
+
+        assert_eq!(
+            "/xyz/hello?next=world",
+            url!(xyz, Slug("hello"), next = Slug("world"))
+        );
+    }
+
+    #[test]
+    fn typescript_works() {
+        let routes = [
+            Route {
+                method: "GET",
+                path: "/posts/:id",
+                handler: "posts::show",
+            },
+            Route {
+                method: "POST",
+                path: "/posts/:id",
+                handler: "posts::show",
+            },
+            Route {
+                method: "GET",
+                path: "/docs/*path",
+                handler: "docs",
+            },
+        ];
+        let output = typescript(&routes);
+
+        assert!(output.contains(
+            "export function posts_show(id: string | number, params?: Record<string, QueryValue | undefined>): string {\n  return `/posts/${encodeURIComponent(String(id))}${query(params)}`;\n}\n"
+        ));
+        assert!(output.contains(
+            "return `/docs/${String(path).split(\"/\").map(encodeURIComponent).join(\"/\")}${query(params)}`;"
+        ));
+        assert_eq!(1, output.matches("export function posts_show").count());
+    }
+
+    #[test]
+    fn route_list_works() {
+        let routes = [
+            Route {
+                method: "POST",
+                path: "/posts",
+                handler: "create_post",
+            },
+            Route {
+                method: "GET",
+                path: "/posts/:id",
+                handler: "posts::show",
+            },
+            Route {
+                method: "GET",
+                path: "/posts",
+                handler: "posts",
+            },
+        ];
+
+        assert_eq!(
+            "GET /posts posts\nPOST /posts create_post\nGET /posts/:id posts::show\n",
+            route_list(&routes)
+        );
+    }
+
+    #[tokio::test]
+    async fn route_table_works() {
+        let routes = [Route {
+            method: "GET",
+            path: "/posts/:id",
+            handler: "posts::show",
+        }];
+
+        assert_eq!(
+            "<table><thead><tr><th>method</th><th>path</th><th>handler</th></tr></thead><tbody><tr><td>GET</td><td>/posts/:id</td><td>posts::show</td></tr></tbody></table>",
+            route_table(&routes).0
+        );
+
+        let app = router().route("/_routes", get(|| async { route_table(ROUTER_ROUTES) }));
+        let (status, body) = make_request(&app, "GET", "/_routes").await;
+        assert_eq!(StatusCode::OK, status);
+        assert!(body.contains("<td>/xyz/:xyz</td><td>xyz</td>"));
+    }
+
+    #[test]
+    fn literal_brace_segments_are_not_params() {
+        let routes = [
+            Route {
+                method: "GET",
+                path: "/calc/{{x}}/:y",
+                handler: "calc",
+            },
+            Route {
+                method: "GET",
+                path: "/about/{{x}}",
+                handler: "about",
+            },
+        ];
+
+        assert!(openapi("t", "1", &routes).contains(r#""parameters":[{"name":"y","#));
+        assert!(!openapi("t", "1", &routes).contains(r#""name":"{x}""#));
+        assert!(sitemap("https://example.com", &routes)
+            .contains("<loc>https://example.com/about/{{x}}</loc>"));
+        assert!(!sitemap("https://example.com", &routes).contains("/calc/"));
+    }
+
+    #[test]
+    fn robots_works() {
+        assert_eq!(
+            "User-agent: *\nAllow: /\nSitemap: https://example.com/sitemap.xml\n",
+            robots("https://example.com/")
+        );
+    }
+
+    #[test]
+    fn sitemap_works() {
+        let routes = [
+            Route {
+                method: "GET",
+                path: "/",
+                handler: "home",
+            },
+            Route {
+                method: "GET",
+                path: "/about",
+                handler: "about",
+            },
+            Route {
+                method: "POST",
+                path: "/about",
+                handler: "contact",
+            },
+            Route {
+                method: "GET",
+                path: "/posts/:id",
+                handler: "post",
+            },
+        ];
+
+        assert_eq!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"><url><loc>https://example.com/</loc></url><url><loc>https://example.com/about</loc></url></urlset>"#,
+            sitemap("https://example.com", &routes)
+        );
+    }
+
+    #[test]
+    fn openapi_works() {
+        let routes = [
+            Route {
+                method: "GET",
+                path: "/posts/:id",
+                handler: "post",
+            },
+            Route {
+                method: "DELETE",
+                path: "/posts/:id",
+                handler: "delete_post",
+            },
+        ];
+
+        assert_eq!(
+            r#"{"openapi":"3.1.0","info":{"title":"Blog \"v2\"","version":"1.0"},"paths":{"/posts/{id}":{"get":{"summary":"post","parameters":[{"name":"id","in":"path","required":true,"schema":{"type":"string"}}],"responses":{"200":{"description":"OK"}}},"delete":{"summary":"delete_post","parameters":[{"name":"id","in":"path","required":true,"schema":{"type":"string"}}],"responses":{"200":{"description":"OK"}}}}}}"#,
+            openapi("Blog \"v2\"", "1.0", &routes)
+        );
+    }
+
+    #[test]
+    fn uri_works() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/search", get(search))
+                .route("/xyz/:xyz", get(xyz))
+        }
+
+        async fn search() {}
+
+        async fn xyz() {}
+
+        let _ = router;
+        let uri = uri!(search, q = "a b&c");
+        assert_eq!("/search", uri.path());
+        assert_eq!(Some("q=a%20b%26c"), uri.query());
+
+        let uri = uri!(xyz, "a b");
+        assert_eq!("/xyz/a%20b", uri.path());
+        assert_eq!(None, uri.query());
+    }
+
+    #[tokio::test]
+    async fn call_works() {
+        let router = router();
+
+        let response = call(&router, "GET", url!(xyz, "a b")).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = call(&router, "DELETE", url!(login)).await;
+        assert_eq!(StatusCode::METHOD_NOT_ALLOWED, response.status());
+    }
+
+    #[tokio::test]
+    async fn get_routes_answer_head() {
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "".into()),
+            make_request(&app, "HEAD", &url!(xyz, "a")).await
+        );
+    }
+
+    #[tokio::test]
+    async fn matched_route_works() {
+        async fn matched(route: MatchedRoute) -> String {
+            format!("{} {:?}", route.route.handler, route.params)
+        }
+
+        let router = Router::new()
+            .route("/xyz/:xyz", get(matched))
+            .layer(axum::Extension(ROUTER_ROUTES));
+
+        assert_eq!(
+            (StatusCode::OK, r#"xyz [("xyz", "a b")]"#.into()),
+            make_request(&router, "GET", "/xyz/a%20b").await
+        );
+    }
+
+    #[tokio::test]
+    async fn query_strings_are_encoded() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/search", get(search))
+        }
+
+        #[derive(Deserialize)]
+        struct Search {
+            q: String,
+        }
+
+        async fn search(Query(params): Query<Search>) -> String {
+            params.q
+        }
+
+        let router = router();
+        let q = "hello world & more=?é";
+        let url = url!(search, q = q);
+
+        assert_eq!("/search?q=hello%20world%20%26%20more%3D%3F%C3%A9", url);
+        assert_eq!(
+            (StatusCode::OK, q.into()),
+            make_request(&router, "GET", &url).await
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_args_can_read_a_url_variable() {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/posts/:id", get(post))
+        }
+
+        async fn post() {}
+
+        struct Link {
+            id: u32,
+            tab: &'static str,
+        }
+
+        let url = Link { id: 1, tab: "a b" };
+
+        let _ = router;
+        assert_eq!("/posts/1?tab=a%20b", url!(post, url.id, tab = url.tab));
+    }
+
+    #[tokio::test]
+    async fn none_query_params_are_skipped() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/filter", get(filter))
+        }
+
+        #[derive(Deserialize)]
+        struct Filter {
+            page: Option<u32>,
+            sort: Option<String>,
+        }
+
+        async fn filter(Query(params): Query<Filter>) -> String {
+            url!(filter, page = params.page, sort = params.sort)
+        }
+
+        let router = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/filter".into()),
+            make_request(&router, "GET", "/filter").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/filter?sort=name".into()),
+            make_request(&router, "GET", "/filter?sort=name").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/filter?page=2&sort=name".into()),
+            make_request(&router, "GET", "/filter?sort=name&page=2").await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn method_override_works() {
+        async fn destroy(body: String) -> String {
+            format!("deleted {}", body)
+        }
+
+        let app =
+            with_method_override(Router::new().route("/posts/:id", axum::routing::delete(destroy)));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/posts/1")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("_method=delete&id=1"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "deleted _method=delete&id=1",
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+
+        assert_eq!(
+            (StatusCode::OK, "deleted ".into()),
+            make_request(&app, "POST", "/posts/1?_method=DELETE").await
+        );
+        assert_eq!(
+            StatusCode::METHOD_NOT_ALLOWED,
+            make_request(&app, "POST", "/posts/1?_method=GET").await.0
+        );
+    }
+
+    #[tokio::test]
+    async fn method_override_streams_large_forms() {
+        async fn upload(request: Request) -> String {
+            let bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            format!("{}", bytes.len())
+        }
+
+        let app =
+            with_method_override(Router::new().route("/uploads", axum::routing::post(upload)));
+        let body = format!("file={}", "a".repeat(3 * 1024 * 1024));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/uploads")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(body.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            body.len().to_string(),
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+    }
+
+    #[test]
+    fn form_attrs_work() {
+        let attrs = form_attrs("delete", "/posts/1");
+
+        assert_eq!("/posts/1", attrs.action);
+        assert_eq!("post", attrs.method);
+        assert_eq!(
+            r#"<input type="hidden" name="_method" value="DELETE">"#,
+            attrs.method_field.html
+        );
+        assert_eq!("get", form_attrs("GET", "/posts").method);
+        assert_eq!("", form_attrs("POST", "/posts").method_field.html);
+    }
+
+    #[tokio::test]
+    async fn request_ids_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/", get(home))
+        }
+
+        async fn home(id: RequestId) -> String {
+            id.to_string()
+        }
+
+        let app = with_request_id(router());
+
+        let response = app.clone().oneshot(request("GET", "/")).await.unwrap();
+        let id = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(id, body);
+        assert_eq!(36, id.len());
+        assert_eq!(Some('4'), id.chars().nth(14));
+
+        let response = app.clone().oneshot(request("GET", "/")).await.unwrap();
+        assert_ne!(response.headers()["x-request-id"], id);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "abc-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["x-request-id"], "abc-123");
+        assert_eq!(
+            "abc-123",
+            response.into_body().collect().await.unwrap().to_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn layers_work() -> Result<(), Box<dyn std::error::Error>> {
+        use axum::middleware::{from_fn, Next};
+
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/", get(public)).route(
+                "/secret",
+                get(secret).layer(from_fn(deny)).layer(from_fn(pass)),
+            )
+        }
+
+        async fn public() -> String {
+            url!(public)
+        }
+
+        async fn secret() -> String {
+            url!(secret)
+        }
+
+        async fn deny(_request: Request, _next: Next) -> StatusCode {
+            StatusCode::UNAUTHORIZED
+        }
+
+        async fn pass(request: Request, next: Next) -> impl IntoResponse {
+            next.run(request).await
+        }
+
+        let app = router();
+
+        assert_eq!("/secret", url!(secret));
+        assert_eq!(
+            (StatusCode::OK, "/".into()),
+            make_request(&app, "GET", "/").await
+        );
+        assert_eq!(
+            (StatusCode::UNAUTHORIZED, "".into()),
+            make_request(&app, "GET", "/secret").await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fallback_works() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/", get(home)).fallback(not_found)
+        }
+
+        async fn home() -> String {
+            url!(home)
+        }
+
+        async fn not_found() -> (StatusCode, &'static str) {
+            (StatusCode::NOT_FOUND, "nothing here")
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/".into()),
+            make_request(&app, "GET", "/").await
+        );
+        assert_eq!(
+            (StatusCode::NOT_FOUND, "nothing here".into()),
+            make_request(&app, "GET", "/nope").await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn path_params_are_encoded() -> Result<(), Box<dyn std::error::Error>> {
+        let router = router();
+
+        assert_eq!("/xyz/a%2Fb%20c%3F%C3%A9", url!(xyz, "a/b c?é"));
+        assert_eq!(
+            (StatusCode::OK, "/xyz/a%2Fb%20c%3F%C3%A9".into()),
+            make_request(&router, "GET", "/xyz/a%2Fb%20c%3F%C3%A9").await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn brace_params_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/orgs/{org_id}/todos/:id", get(org_todo))
+        }
+
+        async fn org_todo(Path((org_id, id)): Path<(u32, u32)>) -> String {
+            url!(org_todo, org_id, id)
+        }
+
+        let app = router();
+
+        assert_eq!("/orgs/1/todos/2", url!(org_todo, 1, 2));
+        assert_eq!(
+            (StatusCode::OK, "/orgs/1/todos/2".into()),
+            make_request(&app, "GET", "/orgs/1/todos/2").await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wildcard_params_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/docs/*path", get(docs))
+        }
+
+        async fn docs(Path(path): Path<String>) -> String {
+            url!(docs, path)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/docs/guide/intro%20page".into()),
+            make_request(&app, "GET", "/docs/guide/intro%20page").await
+        );
+        assert_eq!("/docs/a/b?c=true", url!(docs, path = "a/b", c = true));
+        assert!(ROUTER_ROUTES[0].matches("GET", "/docs/guide/intro"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_handlers_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/posts/:id", get(posts::show))
+                .route("/posts", get(self::posts::index))
+        }
+
+        mod posts {
+            pub async fn index() {}
+
+            pub async fn show() {}
+        }
+
+        let _ = router;
+        assert_eq!("/posts/1", url!(posts::show, 1));
+        assert_eq!("/posts", url!(self::posts::index));
+        assert_eq!("posts::show", ROUTER_ROUTES[0].handler);
+    }
+
+    #[tokio::test]
+    async fn brace_routes_are_served() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/orgs/{org_id}/files/{*path}", get(org_file))
+        }
+
+        async fn org_file(Path((org_id, path)): Path<(u32, String)>) -> String {
+            format!("{} {}", org_id, path)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "1 a/b.txt".into()),
+            make_request(&app, "GET", &url!(org_file, 1, "a/b.txt")).await
+        );
+        assert_eq!("/orgs/:org_id/files/*path", ROUTER_ROUTES[0].path);
+
+        let route = Route {
+            method: "GET",
+            path: "/docs/{*path}",
+            handler: "docs",
+        };
+        assert_eq!(
+            Some(vec![("path", "a/b".into())]),
+            route.params("GET", "/docs/a/b")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn literal_braces_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/calc/{{x}}/:y", get(calc))
+        }
+
+        async fn calc() {}
+
+        let _ = router;
+        assert_eq!("/calc/{{x}}/2", url!(calc, 2));
+        assert_eq!("/calc/{{x}}/2", url!(calc, y = 2));
+    }
+
+    #[tokio::test]
+    async fn trailing_slash_works() -> Result<(), Box<dyn std::error::Error>> {
+        #[router(trailing_slash = "strip")]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(root))
+                .route("/users//new/", get(new_user))
+        }
+
+        async fn root() -> String {
+            url!(root)
+        }
+
+        async fn new_user() -> String {
+            url!(new_user)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/".into()),
+            make_request(&app, "GET", "/").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/users/new".into()),
+            make_request(&app, "GET", "/users/new").await
+        );
+        assert_eq!(
+            (StatusCode::NOT_FOUND, "".into()),
+            make_request(&app, "GET", "/users/new/").await
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn named_path_params_must_be_given() {
+        const PARAMS: &[&str] = &["org_id", "id"];
+
+        assert!(has_path_params(PARAMS, &["id", "tab", "org_id"]));
+        assert!(has_path_params(&[], &["tab"]));
+        assert!(!has_path_params(PARAMS, &["id", "tab"]));
+        assert!(!has_path_params(PARAMS, &["org", "id"]));
+    }
+
+    #[test]
+    fn two_routers_in_one_module_work() {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/", get(home))
+        }
+
+        #[router(prefix = "/v2")]
+        fn api() -> Router {
+            Router::new().route("/posts/:id", get(api_post))
+        }
+
+        async fn home() {}
+
+        async fn api_post() {}
+
+        let _ = (router, api);
+        assert_eq!("/", ROUTER_ROUTES[0].path);
+        assert_eq!("/v2/posts/:id", API_ROUTES[0].path);
+        assert_eq!("/v2/posts/1", url!(api_post, 1));
+    }
+
+    #[tokio::test]
+    async fn merge_works() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(public))
+                .merge(Router::new().route("/account/:id", get(account)))
+        }
+
+        async fn public() -> String {
+            url!(public)
+        }
+
+        async fn account(Path(id): Path<u32>) -> String {
+            url!(account, id)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/account/3".into()),
+            make_request(&app, "GET", "/account/3").await
+        );
+        assert_eq!(
+            vec!["/", "/account/:id"],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| route.path)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn root_paths_join_correctly() -> Result<(), Box<dyn std::error::Error>> {
+        #[router(prefix = "/api/")]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(api_home))
+                .nest("/admin", Router::new().route("/", get(admin_home)))
+        }
+
+        async fn api_home() -> String {
+            url!(api_home)
+        }
+
+        async fn admin_home() -> String {
+            url!(admin_home)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/api".into()),
+            make_request(&app, "GET", "/api").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/api/admin".into()),
+            make_request(&app, "GET", "/api/admin").await
+        );
+        assert_eq!(
+            vec!["/api", "/api/admin"],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| route.path)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn any_and_on_work() -> Result<(), Box<dyn std::error::Error>> {
+        #[router]
+        fn router() -> Router {
+            Router::new().route("/anything", any(anything)).route(
+                "/change",
+                on(MethodFilter::PUT.or(MethodFilter::DELETE), change),
+            )
+        }
+
+        async fn anything() -> String {
+            url!(anything)
+        }
+
+        async fn change() -> String {
+            url!(change)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/anything".into()),
+            make_request(&app, "PATCH", "/anything").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/change".into()),
+            make_request(&app, "DELETE", "/change").await
+        );
+        assert_eq!(
+            vec!["ANY", "PUT", "DELETE"],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| route.method)
+                .collect::<Vec<_>>()
+        );
+        assert!(ROUTER_ROUTES[0].matches("PATCH", "/anything"));
+        assert_eq!(None, ROUTER_ROUTES[0].http_method());
+        assert_eq!(
+            Some(axum::http::Method::PUT),
+            ROUTER_ROUTES[1].http_method()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn method_filters_work() -> Result<(), Box<dyn std::error::Error>> {
+        const WRITE: MethodFilter = MethodFilter::POST;
+
+        #[router]
+        fn router() -> Router {
+            Router::new()
+                .route("/read", on(MethodFilter::GET.or(MethodFilter::HEAD), read))
+                .route("/write", on(WRITE, write))
+                .route("/search", on_method("QUERY", search))
+        }
+
+        async fn read() {}
+
+        async fn write() {}
+
+        async fn search() -> String {
+            url!(search)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            vec!["GET", "HEAD", "ANY", "QUERY"],
+            ROUTER_ROUTES
+                .iter()
+                .map(|route| route.method)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            (StatusCode::OK, "/search".into()),
+            make_request(&app, "QUERY", "/search").await
+        );
+        assert_eq!(
+            StatusCode::METHOD_NOT_ALLOWED,
+            make_request(&app, "GET", "/search").await.0
+        );
+        assert_eq!(
+            Some(axum::http::Method::from_bytes(b"QUERY")?),
+            ROUTER_ROUTES[3].http_method()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prefix_works() -> Result<(), Box<dyn std::error::Error>> {
+        #[router(prefix = "/api/v1/")]
+        fn router() -> Router {
+            Router::new()
+                .route("/", get(api))
+                .route("/users/:id", get(api_user))
+        }
+
+        async fn api() -> String {
+            url!(api)
+        }
+
+        async fn api_user(Path(id): Path<u32>) -> String {
+            url!(api_user, id)
+        }
+
+        let app = router();
+
+        assert_eq!(
+            (StatusCode::OK, "/api/v1".into()),
+            make_request(&app, "GET", "/api/v1").await
+        );
+        assert_eq!(
+            (StatusCode::OK, "/api/v1/users/5".into()),
+            make_request(&app, "GET", "/api/v1/users/5").await
+        );
+        assert_eq!(
+            (StatusCode::NOT_FOUND, "".into()),
+            make_request(&app, "GET", "/users/5").await
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn state_works() -> Result<(), Box<dyn std::error::Error>> {
         use axum::extract::State;